@@ -2,9 +2,27 @@ fn main() {
     #[cfg(not(debug_assertions))]
     download_ffmpeg();
 
+    set_library_rpath();
+
     tauri_build::build()
 }
 
+/// llama.cpp is linked dynamically. The Linux and macOS bundles ship its libraries as resources,
+/// so the binary looks for them in the resource directory.
+fn set_library_rpath() {
+    match std::env::var("CARGO_CFG_TARGET_OS").as_deref() {
+        // Resources are installed to /usr/lib/<productName>. DT_RPATH rather than DT_RUNPATH, so
+        // it also applies when the libraries load each other.
+        Ok("linux") => println!(
+            "cargo:rustc-link-arg-bins=-Wl,--disable-new-dtags,-rpath,$ORIGIN/../lib/Shiori"
+        ),
+        Ok("macos") => {
+            println!("cargo:rustc-link-arg-bins=-Wl,-rpath,@executable_path/../Resources")
+        }
+        _ => {}
+    }
+}
+
 #[cfg(not(debug_assertions))]
 fn download_ffmpeg() {
     use std::env;
@@ -15,9 +33,9 @@ fn download_ffmpeg() {
     use std::path::PathBuf;
 
     let bin_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("bin");
-    let ffmpeg_path = bin_dir.join("ffmpeg.exe");
+    let binaries = ["ffmpeg.exe", "ffprobe.exe"];
 
-    if ffmpeg_path.exists() {
+    if binaries.iter().all(|name| bin_dir.join(name).exists()) {
         println!("cargo:warning=ffmpeg.exe already exists, skipping download");
         return;
     }
@@ -40,14 +58,13 @@ fn download_ffmpeg() {
     for i in 0..zip.len() {
         let mut file = zip.by_index(i).expect("Failed to get zip");
 
-        if file.name().ends_with("ffmpeg.exe") {
-            println!("cargo:warning=Extracting ffmpeg.exe...");
-
-            let mut out_file =
-                fs::File::create(&ffmpeg_path).expect("Failed to create output file");
-            copy(&mut file, &mut out_file).expect("Failed to copy ffmpeg.exe");
+        let Some(name) = binaries.iter().find(|name| file.name().ends_with(*name)) else {
+            continue;
+        };
+        println!("cargo:warning=Extracting {}...", name);
 
-            break;
-        }
+        let mut out_file =
+            fs::File::create(bin_dir.join(name)).expect("Failed to create output file");
+        copy(&mut file, &mut out_file).expect("Failed to copy binary");
     }
 }
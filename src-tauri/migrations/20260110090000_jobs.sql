@@ -0,0 +1,25 @@
+-- Summarization jobs, persisted so they can be resumed after the app restarts
+CREATE TABLE IF NOT EXISTS jobs (
+    id TEXT PRIMARY KEY,
+    file_path TEXT NOT NULL,
+    language TEXT NOT NULL,
+    status TEXT NOT NULL,
+    stage TEXT NOT NULL,
+    audio_path TEXT,
+    transcript TEXT,
+    summary TEXT,
+    summary_id TEXT,
+    error TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (summary_id) REFERENCES summaries(id) ON DELETE SET NULL
+);
+
+CREATE TRIGGER jobs_updated_at
+AFTER UPDATE ON jobs
+FOR EACH ROW
+BEGIN
+    UPDATE jobs
+    SET updated_at = CURRENT_TIMESTAMP
+    WHERE id = OLD.id;
+END;
@@ -0,0 +1,4 @@
+-- Jobs queued together by a batch summarization share a batch id
+ALTER TABLE jobs ADD COLUMN batch_id TEXT;
+
+CREATE INDEX idx_jobs_batch_id ON jobs(batch_id);
@@ -0,0 +1,27 @@
+-- Folders watched for new recordings, summarized automatically with the folder defaults
+CREATE TABLE IF NOT EXISTS watched_folders (
+    id TEXT PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE,
+    language TEXT NOT NULL,
+    template TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER watched_folders_updated_at
+AFTER UPDATE ON watched_folders
+FOR EACH ROW
+BEGIN
+    UPDATE watched_folders
+    SET updated_at = CURRENT_TIMESTAMP
+    WHERE id = OLD.id;
+END;
+
+-- Content hashes of files already queued from a watched folder
+CREATE TABLE IF NOT EXISTS ingested_files (
+    hash TEXT PRIMARY KEY,
+    file_path TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE jobs ADD COLUMN template TEXT;
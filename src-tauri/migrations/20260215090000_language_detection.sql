@@ -0,0 +1,3 @@
+-- Confidence of the automatic language detection, NULL when the language was picked by the user
+ALTER TABLE summaries ADD COLUMN language_confidence REAL;
+ALTER TABLE jobs ADD COLUMN language_confidence REAL;
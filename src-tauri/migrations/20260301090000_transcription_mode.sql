@@ -0,0 +1,8 @@
+-- Whether the transcript was translated to English by whisper
+ALTER TABLE summaries ADD COLUMN transcription_mode TEXT NOT NULL DEFAULT 'transcribe';
+-- Marks segments in the spoken language kept next to a translated transcript
+ALTER TABLE summary_transcripts ADD COLUMN is_original INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE jobs ADD COLUMN transcription_mode TEXT NOT NULL DEFAULT 'transcribe';
+ALTER TABLE jobs ADD COLUMN keep_original_transcript INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE jobs ADD COLUMN original_transcript TEXT;
@@ -0,0 +1,13 @@
+-- Word-level timing and confidence of a transcript segment
+CREATE TABLE IF NOT EXISTS transcript_words (
+    id TEXT PRIMARY KEY,
+    transcript_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    "text" TEXT NOT NULL,
+    start_time REAL NOT NULL,
+    end_time REAL NOT NULL,
+    probability REAL NOT NULL,
+    FOREIGN KEY (transcript_id) REFERENCES summary_transcripts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_transcript_words_transcript_id ON transcript_words(transcript_id);
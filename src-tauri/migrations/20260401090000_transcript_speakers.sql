@@ -0,0 +1,2 @@
+-- Speaker label assigned by diarization, NULL when diarization was off
+ALTER TABLE summary_transcripts ADD COLUMN speaker TEXT;
@@ -0,0 +1,58 @@
+-- Voices enrolled by the user, used to name known speakers in new recordings
+CREATE TABLE IF NOT EXISTS voice_profiles (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    embedding TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER voice_profiles_updated_at
+AFTER UPDATE ON voice_profiles
+FOR EACH ROW
+BEGIN
+    UPDATE voice_profiles
+    SET updated_at = CURRENT_TIMESTAMP
+    WHERE id = OLD.id;
+END;
+
+-- Speakers found by diarization in a summary
+CREATE TABLE IF NOT EXISTS speakers (
+    id TEXT PRIMARY KEY,
+    summary_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    profile_id TEXT,
+    embedding TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (summary_id) REFERENCES summaries(id) ON DELETE CASCADE,
+    FOREIGN KEY (profile_id) REFERENCES voice_profiles(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_speakers_summary_id ON speakers(summary_id);
+
+CREATE TRIGGER speakers_updated_at
+AFTER UPDATE ON speakers
+FOR EACH ROW
+BEGIN
+    UPDATE speakers
+    SET updated_at = CURRENT_TIMESTAMP
+    WHERE id = OLD.id;
+END;
+
+ALTER TABLE summary_transcripts ADD COLUMN speaker_id TEXT REFERENCES speakers(id) ON DELETE SET NULL;
+ALTER TABLE jobs ADD COLUMN speakers TEXT;
+
+-- Summaries diarized before speakers had their own table keep their labels, without a voice
+INSERT INTO speakers (id, summary_id, name)
+SELECT randomblob(16), summary_id, speaker
+FROM summary_transcripts
+WHERE speaker IS NOT NULL
+GROUP BY summary_id, speaker;
+
+UPDATE summary_transcripts
+SET speaker_id = (
+    SELECT s.id FROM speakers s
+    WHERE s.summary_id = summary_transcripts.summary_id AND s.name = summary_transcripts.speaker
+)
+WHERE speaker IS NOT NULL;
@@ -0,0 +1,28 @@
+-- Custom vocabulary passed to whisper and used to correct transcripts
+CREATE TABLE IF NOT EXISTS glossaries (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    is_global BOOLEAN NOT NULL DEFAULT 0,
+    -- JSON array of {"term": ..., "misspellings": [...]}
+    terms TEXT NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER glossaries_updated_at
+AFTER UPDATE ON glossaries
+FOR EACH ROW
+BEGIN
+    UPDATE glossaries
+    SET updated_at = CURRENT_TIMESTAMP
+    WHERE id = OLD.id;
+END;
+
+-- Glossaries picked for a single summarize run, on top of the global ones
+CREATE TABLE IF NOT EXISTS job_glossaries (
+    job_id TEXT NOT NULL,
+    glossary_id TEXT NOT NULL,
+    PRIMARY KEY (job_id, glossary_id),
+    FOREIGN KEY (job_id) REFERENCES jobs(id) ON DELETE CASCADE,
+    FOREIGN KEY (glossary_id) REFERENCES glossaries(id) ON DELETE CASCADE
+);
@@ -0,0 +1,5 @@
+-- Model and whisper decoding settings a transcript was produced with
+ALTER TABLE jobs ADD COLUMN speech_to_text_model TEXT;
+ALTER TABLE jobs ADD COLUMN transcription_settings TEXT;
+ALTER TABLE summaries ADD COLUMN speech_to_text_model TEXT;
+ALTER TABLE summaries ADD COLUMN transcription_settings TEXT;
@@ -0,0 +1,3 @@
+-- Metadata of the source file, read by probing it before decoding
+ALTER TABLE jobs ADD COLUMN media_info TEXT;
+ALTER TABLE summaries ADD COLUMN media_info TEXT;
@@ -0,0 +1,4 @@
+-- Audio streams and channels picked for a run, and the tracks decoded for it
+ALTER TABLE jobs ADD COLUMN audio_selection TEXT;
+ALTER TABLE jobs ADD COLUMN audio_tracks TEXT;
+ALTER TABLE summaries ADD COLUMN audio_selection TEXT;
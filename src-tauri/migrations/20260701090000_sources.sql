@@ -0,0 +1,7 @@
+-- Concatenated input files and the transcribed range of their combined timeline
+ALTER TABLE jobs ADD COLUMN sources TEXT;
+ALTER TABLE jobs ADD COLUMN start_time REAL;
+ALTER TABLE jobs ADD COLUMN end_time REAL;
+ALTER TABLE summaries ADD COLUMN sources TEXT;
+ALTER TABLE summaries ADD COLUMN start_time REAL;
+ALTER TABLE summaries ADD COLUMN end_time REAL;
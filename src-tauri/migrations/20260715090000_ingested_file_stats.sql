@@ -0,0 +1,5 @@
+-- Size and modification time of ingested files, so unchanged files are not hashed again
+ALTER TABLE ingested_files ADD COLUMN file_size INTEGER;
+ALTER TABLE ingested_files ADD COLUMN modified_at INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_ingested_files_file_path ON ingested_files (file_path);
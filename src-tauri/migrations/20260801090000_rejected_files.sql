@@ -0,0 +1,8 @@
+-- Files in watched folders that are not supported recordings, so they are not probed again
+-- on every startup while they stay unchanged
+CREATE TABLE IF NOT EXISTS rejected_files (
+    file_path TEXT PRIMARY KEY,
+    file_size INTEGER NOT NULL,
+    modified_at INTEGER,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
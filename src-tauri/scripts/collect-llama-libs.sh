@@ -0,0 +1,21 @@
+#!/bin/sh
+# Copies the shared llama.cpp libraries of the release build into target/release/llama, where
+# the Linux and macOS bundles pick them up. Only the names the binary links against are kept
+# (libllama.so.0, libllama.0.dylib), resolved from the symlinks CMake installs them as.
+set -e
+
+cd "$(dirname "$0")/.."
+lib_dir=$(ls -td target/release/build/llama-cpp-sys-2-*/out/lib 2>/dev/null | head -n 1)
+if [ -z "$lib_dir" ]; then
+    echo "llama.cpp libraries not found, run the release build first" >&2
+    exit 1
+fi
+
+rm -rf target/release/llama
+mkdir -p target/release/llama
+for lib in "$lib_dir"/lib*; do
+    case "$(basename "$lib")" in
+        *.so.*.* | *.*.*.dylib) ;;
+        *.so.* | *.*.dylib) cp -L "$lib" target/release/llama/ ;;
+    esac
+done
@@ -11,10 +11,13 @@ use crate::{
     features::{
         chat::entities::Chat,
         model::text_generation::{get_text_generation, Message, Role},
+        summarize::{entities::SummaryTranscript, summarizer::estimate_tokens},
     },
-    utils::tauri::get_settings_store,
 };
 
+/// Largest speaker-labelled transcript that is put into the system prompt as is.
+const MAX_TRANSCRIPT_TOKENS: usize = 4000;
+
 #[tauri::command]
 pub async fn get_chats(database: State<'_, SqlitePool>) -> Result<Vec<Chat>, ErrorCode> {
     let pool = database.inner();
@@ -26,6 +29,60 @@ pub async fn get_chats(database: State<'_, SqlitePool>) -> Result<Vec<Chat>, Err
     Ok(chats)
 }
 
+/// Tells the assistant who spoke in a diarized recording. Short transcripts are included in
+/// full so questions like "what did Speaker 2 promise?" can be answered, longer ones only
+/// list the participants. Empty when the transcript has no speaker labels.
+async fn speaker_context(pool: &SqlitePool, summary_id: Uuid) -> anyhow::Result<String> {
+    let transcripts = sqlx::query_as::<_, SummaryTranscript>(
+        "SELECT * FROM summary_transcripts WHERE summary_id = ? AND is_original = 0 ORDER BY start_time ASC",
+    )
+    .bind(summary_id)
+    .fetch_all(pool)
+    .await
+    .context("Failed to fetch summary transcripts")?;
+
+    let mut speakers: Vec<&str> = Vec::new();
+    for speaker in transcripts.iter().filter_map(|t| t.speaker.as_deref()) {
+        if !speakers.contains(&speaker) {
+            speakers.push(speaker);
+        }
+    }
+    if speakers.is_empty() {
+        return Ok(String::new());
+    }
+
+    let transcript = transcripts
+        .iter()
+        .map(|t| match &t.speaker {
+            Some(speaker) => format!("{}: {}", speaker, t.text.trim()),
+            None => t.text.trim().to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let context = if estimate_tokens(&transcript) <= MAX_TRANSCRIPT_TOKENS {
+        format!(
+            "
+            The recording has multiple speakers. This is the transcript, where each line
+            starts with the label of the person speaking:
+            ---
+            {}
+            ---
+            ",
+            transcript
+        )
+    } else {
+        format!(
+            "
+            The recording has multiple speakers: {}.
+            ",
+            speakers.join(", ")
+        )
+    };
+
+    Ok(context)
+}
+
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct MessageChunk {
@@ -56,6 +113,8 @@ pub async fn send_message(
             .await
             .context("Failed to fetch summary")?;
 
+        let speakers = speaker_context(&pool, summary_id).await?;
+
         let prompt = format!(
             "
             You are a personal assistant helping the user based on a summarized voice note.
@@ -82,8 +141,8 @@ pub async fn send_message(
             ---
             {}
             ---
-            ",
-            summary
+            {}",
+            summary, speakers
         );
         chats.push(Chat {
             id: Uuid::new_v4(),
@@ -129,8 +188,7 @@ pub async fn send_message(
     let mut reply_chat_for_task = new_reply_chat.clone();
     tokio::spawn(async move {
         if let Err(e) = (async {
-            let settings = get_settings_store(&app).context("Failed to get settings store")?;
-            let text_generation = get_text_generation(settings.as_ref())
+            let text_generation = get_text_generation(&app)
                 .await
                 .context("Failed to get text generation model")?;
 
@@ -142,11 +200,9 @@ pub async fn send_message(
                 })
                 .collect::<Vec<Message>>();
 
-            let text_stream = text_generation
+            let mut text_stream = text_generation
                 .generate_text_stream(conversation, tokio_util::sync::CancellationToken::new());
 
-            futures_util::pin_mut!(text_stream);
-
             let mut message = String::new();
 
             while let Some(chunk) = text_stream.next().await {
@@ -0,0 +1,129 @@
+use anyhow::Context;
+use sqlx::SqlitePool;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::ErrorCode,
+    features::glossary::entities::{Glossary, GlossaryTerm},
+};
+
+/// Trims the input and drops empty terms and misspellings.
+fn normalize(
+    name: String,
+    terms: Vec<GlossaryTerm>,
+) -> Result<(String, Vec<GlossaryTerm>), ErrorCode> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(ErrorCode::invalid_input("Glossary name must not be empty"));
+    }
+
+    let terms = terms
+        .into_iter()
+        .map(|term| GlossaryTerm {
+            term: term.term.trim().to_string(),
+            misspellings: term
+                .misspellings
+                .into_iter()
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+                .collect(),
+        })
+        .filter(|term| !term.term.is_empty())
+        .collect();
+
+    Ok((name, terms))
+}
+
+async fn fetch_glossary(database: &SqlitePool, glossary_id: Uuid) -> Result<Glossary, ErrorCode> {
+    sqlx::query_as::<_, Glossary>("SELECT * FROM glossaries WHERE id = ?")
+        .bind(glossary_id)
+        .fetch_optional(database)
+        .await
+        .context("Failed to fetch glossary from database")?
+        .ok_or_else(|| ErrorCode::NotFound(format!("Glossary with id {} not found", glossary_id)))
+}
+
+#[tauri::command]
+pub async fn get_glossaries(database: State<'_, SqlitePool>) -> Result<Vec<Glossary>, ErrorCode> {
+    let glossaries =
+        sqlx::query_as::<_, Glossary>("SELECT * FROM glossaries ORDER BY created_at ASC")
+            .fetch_all(database.inner())
+            .await
+            .context("Failed to fetch glossaries from database")?;
+
+    Ok(glossaries)
+}
+
+#[tauri::command]
+pub async fn create_glossary(
+    database: State<'_, SqlitePool>,
+    name: String,
+    is_global: bool,
+    terms: Vec<GlossaryTerm>,
+) -> Result<Glossary, ErrorCode> {
+    let (name, terms) = normalize(name, terms)?;
+    let glossary_id = Uuid::new_v4();
+
+    sqlx::query("INSERT INTO glossaries (id, name, is_global, terms) VALUES (?, ?, ?, ?)")
+        .bind(glossary_id)
+        .bind(&name)
+        .bind(is_global)
+        .bind(serde_json::to_string(&terms).context("Failed to serialize glossary terms")?)
+        .execute(database.inner())
+        .await
+        .context("Failed to insert glossary into database")?;
+
+    fetch_glossary(database.inner(), glossary_id).await
+}
+
+#[tauri::command]
+pub async fn update_glossary(
+    database: State<'_, SqlitePool>,
+    glossary_id: Uuid,
+    name: String,
+    is_global: bool,
+    terms: Vec<GlossaryTerm>,
+) -> Result<Glossary, ErrorCode> {
+    let (name, terms) = normalize(name, terms)?;
+
+    let result =
+        sqlx::query("UPDATE glossaries SET name = ?, is_global = ?, terms = ? WHERE id = ?")
+            .bind(&name)
+            .bind(is_global)
+            .bind(serde_json::to_string(&terms).context("Failed to serialize glossary terms")?)
+            .bind(glossary_id)
+            .execute(database.inner())
+            .await
+            .context("Failed to update glossary")?;
+
+    if result.rows_affected() == 0 {
+        return Err(ErrorCode::NotFound(format!(
+            "Glossary with id {} not found",
+            glossary_id
+        )));
+    }
+
+    fetch_glossary(database.inner(), glossary_id).await
+}
+
+#[tauri::command]
+pub async fn delete_glossary(
+    database: State<'_, SqlitePool>,
+    glossary_id: Uuid,
+) -> Result<(), ErrorCode> {
+    let result = sqlx::query("DELETE FROM glossaries WHERE id = ?")
+        .bind(glossary_id)
+        .execute(database.inner())
+        .await
+        .context("Failed to delete glossary")?;
+
+    if result.rows_affected() == 0 {
+        return Err(ErrorCode::NotFound(format!(
+            "Glossary with id {} not found",
+            glossary_id
+        )));
+    }
+
+    Ok(())
+}
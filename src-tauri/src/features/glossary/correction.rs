@@ -0,0 +1,322 @@
+use std::ops::Range;
+
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::features::{
+    glossary::entities::{Glossary, GlossaryTerm},
+    model::speech_to_text::{Segment, Word},
+    summarize::summarizer::estimate_tokens,
+};
+
+/// Whisper keeps at most half of its 448 token text context as prompt. Stay well below that,
+/// since the token estimate is rough and the prompt is cut from the front when too long.
+const MAX_PROMPT_TOKENS: usize = 150;
+
+/// Terms of the global glossaries and of the glossaries picked for the job.
+pub async fn get_job_terms(database: &SqlitePool, job_id: Uuid) -> Result<Vec<GlossaryTerm>> {
+    let glossaries = sqlx::query_as::<_, Glossary>(
+        "SELECT * FROM glossaries WHERE is_global = 1 OR id IN (SELECT glossary_id FROM job_glossaries WHERE job_id = ?) ORDER BY created_at ASC",
+    )
+    .bind(job_id)
+    .fetch_all(database)
+    .await
+    .context("Failed to fetch glossaries from database")?;
+
+    Ok(glossaries
+        .into_iter()
+        .flat_map(|glossary| glossary.terms)
+        .collect())
+}
+
+/// Lists the glossary terms as whisper's initial prompt, which makes it prefer their spelling.
+pub fn initial_prompt(terms: &[GlossaryTerm]) -> Option<String> {
+    let mut prompt_terms: Vec<&str> = Vec::new();
+    let mut tokens = 0;
+    for term in terms.iter().map(|t| t.term.trim()) {
+        if term.is_empty() || prompt_terms.contains(&term) {
+            continue;
+        }
+        tokens += estimate_tokens(term) + 1;
+        if tokens > MAX_PROMPT_TOKENS {
+            break;
+        }
+        prompt_terms.push(term);
+    }
+
+    (!prompt_terms.is_empty()).then(|| format!("{}.", prompt_terms.join(", ")))
+}
+
+/// Whether words spelled like the term are matched too, to fix their capitalization. A single
+/// word that is merely capitalized, like "Teams" or "Go", is also an ordinary word, so only
+/// terms with several words or with inner capitals or digits qualify.
+fn fixes_capitalization(term: &str) -> bool {
+    term.split_whitespace().nth(1).is_some()
+        || term
+            .chars()
+            .skip(1)
+            .any(|c| c.is_uppercase() || c.is_numeric())
+}
+
+/// Patterns to replace and their replacement, longest first so "shiori app" is not cut short
+/// by a replacement of "shiori".
+fn replacements(terms: &[GlossaryTerm]) -> Vec<(Vec<char>, &str)> {
+    let mut replacements = terms
+        .iter()
+        .flat_map(|term| {
+            let own_spelling = fixes_capitalization(term.term.trim()).then_some(&term.term);
+            term.misspellings
+                .iter()
+                .chain(own_spelling)
+                .map(|pattern| (pattern.trim(), term.term.trim()))
+        })
+        .filter(|(pattern, term)| !pattern.is_empty() && !term.is_empty())
+        .map(|(pattern, term)| (pattern.chars().collect::<Vec<char>>(), term))
+        .collect::<Vec<_>>();
+    replacements.sort_by_key(|(pattern, _)| std::cmp::Reverse(pattern.len()));
+    replacements
+}
+
+/// Replaces misspellings with their glossary term and fixes the capitalization of terms that
+/// qualify for it. Matching ignores case and only replaces whole words. Words merged by a
+/// replacement spanning several of them become one word.
+pub fn correct_segments(segments: &mut [Segment], terms: &[GlossaryTerm]) {
+    let replacements = replacements(terms);
+    if replacements.is_empty() {
+        return;
+    }
+
+    for segment in segments.iter_mut() {
+        let chars = segment.text.chars().collect::<Vec<char>>();
+        let matches = find_matches(&chars, &replacements);
+        segment.text = render(&chars, 0..chars.len(), &matches);
+        correct_words(&mut segment.words, &replacements);
+    }
+}
+
+/// Applies the replacements to the words of a segment, joined the way they read in its text.
+fn correct_words(words: &mut Vec<Word>, replacements: &[(Vec<char>, &str)]) {
+    let mut chars: Vec<char> = Vec::new();
+    let mut spans: Vec<Range<usize>> = Vec::with_capacity(words.len());
+    for word in words.iter() {
+        let first = word.text.chars().next();
+        let joined = chars
+            .last()
+            .zip(first)
+            .is_some_and(|(a, b)| is_spaceless(*a) && is_spaceless(b));
+        if !chars.is_empty() && !joined {
+            chars.push(' ');
+        }
+        let start = chars.len();
+        chars.extend(word.text.chars());
+        spans.push(start..chars.len());
+    }
+
+    let matches = find_matches(&chars, replacements);
+    if matches.is_empty() {
+        return;
+    }
+
+    let mut corrected = Vec::with_capacity(words.len());
+    let mut first = 0;
+    while first < words.len() {
+        // A match that runs into the next word takes that word along
+        let mut last = first;
+        while last + 1 < words.len()
+            && matches
+                .iter()
+                .any(|m| m.start < spans[last].end && m.end > spans[last + 1].start)
+        {
+            last += 1;
+        }
+
+        corrected.push(Word {
+            text: render(&chars, spans[first].start..spans[last].end, &matches),
+            start: words[first].start,
+            end: words[last].end,
+            probability: words[first..=last]
+                .iter()
+                .map(|word| word.probability)
+                .fold(f32::INFINITY, f32::min),
+        });
+        first = last + 1;
+    }
+
+    *words = corrected;
+}
+
+/// Scripts written without spaces, like Chinese or Japanese, have no word boundaries to check.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() && (c as u32) < 0x2E80
+}
+
+fn is_spaceless(c: char) -> bool {
+    c.is_alphanumeric() && (c as u32) >= 0x2E80
+}
+
+struct Match<'a> {
+    start: usize,
+    end: usize,
+    replacement: &'a str,
+}
+
+/// Finds whole-word matches in one left-to-right pass. Replaced text is skipped rather than
+/// searched again, so the output of one term is never rewritten by another.
+fn find_matches<'a>(chars: &[char], replacements: &[(Vec<char>, &'a str)]) -> Vec<Match<'a>> {
+    let mut matches = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        let found = replacements.iter().find(|(pattern, _)| {
+            let end = i + pattern.len();
+            end <= chars.len()
+                && chars[i..end]
+                    .iter()
+                    .zip(pattern)
+                    .all(|(a, b)| a == b || a.to_lowercase().eq(b.to_lowercase()))
+                && !(i > 0 && is_word_char(chars[i - 1]) && is_word_char(pattern[0]))
+                && !(end < chars.len()
+                    && is_word_char(chars[end])
+                    && is_word_char(pattern[pattern.len() - 1]))
+        });
+
+        match found {
+            Some((pattern, replacement)) => {
+                matches.push(Match {
+                    start: i,
+                    end: i + pattern.len(),
+                    replacement,
+                });
+                i += pattern.len();
+            }
+            None => i += 1,
+        }
+    }
+
+    matches
+}
+
+/// Text of `range` with the matches inside it replaced.
+fn render(chars: &[char], range: Range<usize>, matches: &[Match]) -> String {
+    let mut result = String::with_capacity(range.len());
+    let mut i = range.start;
+    for m in matches
+        .iter()
+        .filter(|m| m.start >= range.start && m.end <= range.end)
+    {
+        result.extend(&chars[i..m.start]);
+        result.push_str(m.replacement);
+        i = m.end;
+    }
+    result.extend(&chars[i..range.end]);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(term: &str, misspellings: &[&str]) -> GlossaryTerm {
+        GlossaryTerm {
+            term: term.to_string(),
+            misspellings: misspellings.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    fn segment(text: &str, words: &[&str]) -> Segment {
+        Segment {
+            text: text.to_string(),
+            start: 0.0,
+            end: 1.0,
+            words: words
+                .iter()
+                .enumerate()
+                .map(|(i, word)| Word {
+                    text: word.to_string(),
+                    start: i as f64,
+                    end: i as f64 + 1.0,
+                    probability: 0.9,
+                })
+                .collect(),
+            speaker: None,
+        }
+    }
+
+    fn replace(text: &str, terms: &[GlossaryTerm]) -> String {
+        let chars = text.chars().collect::<Vec<char>>();
+        let replacements = replacements(terms);
+        render(&chars, 0..chars.len(), &find_matches(&chars, &replacements))
+    }
+
+    #[test]
+    fn test_replace_words() {
+        assert_eq!(
+            replace("ask shiori, not shioriko", &[term("Shiori", &["shiori"])]),
+            "ask Shiori, not shioriko"
+        );
+        assert_eq!(replace("SHIORI", &[term("Shiori", &["shiori"])]), "Shiori");
+        assert_eq!(
+            replace("今日はしおりです", &[term("栞", &["しおり"])]),
+            "今日は栞です"
+        );
+    }
+
+    #[test]
+    fn test_common_words_are_kept() {
+        let terms = [term("Teams", &[]), term("GitHub", &[])];
+        assert_eq!(
+            replace("both teams use github", &terms),
+            "both teams use GitHub"
+        );
+    }
+
+    #[test]
+    fn test_replacements_are_not_chained() {
+        // "Notion" is itself a misspelling of another term, but replaced text is not revisited
+        let terms = [term("Notion", &["no shun"]), term("Motion", &["notion"])];
+        assert_eq!(replace("no shun and notion", &terms), "Notion and Motion");
+    }
+
+    #[test]
+    fn test_correct_words() {
+        let mut segments = vec![segment(
+            " Show he oh ray, hi",
+            &["Show", "he", "oh", "ray,", "hi"],
+        )];
+
+        correct_segments(&mut segments, &[term("Shiori", &["show he oh ray"])]);
+
+        let words = &segments[0].words;
+        assert_eq!(segments[0].text, " Shiori, hi");
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].text, "Shiori,");
+        assert_eq!((words[0].start, words[0].end), (0.0, 4.0));
+        assert_eq!(words[1].text, "hi");
+    }
+
+    #[test]
+    fn test_correct_segments() {
+        let mut segments = vec![segment(
+            " Show he oh ray ships with the new shiori app",
+            &[],
+        )];
+
+        correct_segments(
+            &mut segments,
+            &[term("Shiori", &["show he oh ray"]), term("Shiori App", &[])],
+        );
+
+        assert_eq!(segments[0].text, " Shiori ships with the new Shiori App");
+    }
+
+    #[test]
+    fn test_initial_prompt() {
+        assert_eq!(initial_prompt(&[]), None);
+        assert_eq!(
+            initial_prompt(&[term("Shiori", &[]), term("Aiko", &[]), term("Shiori", &[])]),
+            Some("Shiori, Aiko.".to_string())
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, FromRow, Row};
+use uuid::Uuid;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlossaryTerm {
+    /// Correct spelling. Terms of several words or with inner capitals, like "GitHub", also fix
+    /// the capitalization of matching words
+    pub term: String,
+    /// Spellings whisper tends to produce instead, replaced by `term`
+    #[serde(default)]
+    pub misspellings: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Glossary {
+    pub id: Uuid,
+    pub name: String,
+    /// Applied to every transcription, not only to runs that pick it
+    pub is_global: bool,
+    pub terms: Vec<GlossaryTerm>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl FromRow<'_, SqliteRow> for Glossary {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        let terms = row.try_get::<String, _>("terms")?;
+        let terms = serde_json::from_str(&terms).map_err(|e| sqlx::Error::ColumnDecode {
+            index: "terms".to_string(),
+            source: Box::new(e),
+        })?;
+
+        Ok(Glossary {
+            id: row.try_get("id")?,
+            name: row.try_get("name")?,
+            is_global: row.try_get("is_global")?,
+            terms,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+        })
+    }
+}
@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::features::job::entities::{Job, JobStatus};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReportItem {
+    pub job_id: Uuid,
+    pub file_path: String,
+    pub status: JobStatus,
+    pub summary_id: Option<Uuid>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReport {
+    pub batch_id: Uuid,
+    pub total: usize,
+    /// Jobs that are still queued or running
+    pub pending: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    pub items: Vec<BatchReportItem>,
+}
+
+impl BatchReport {
+    pub fn from_jobs(batch_id: Uuid, jobs: Vec<Job>) -> Self {
+        let count = |status: JobStatus| jobs.iter().filter(|job| job.status == status).count();
+        let completed = count(JobStatus::Completed);
+        let failed = count(JobStatus::Failed);
+        let cancelled = count(JobStatus::Cancelled);
+
+        BatchReport {
+            batch_id,
+            total: jobs.len(),
+            pending: jobs.len() - completed - failed - cancelled,
+            completed,
+            failed,
+            cancelled,
+            items: jobs
+                .into_iter()
+                .map(|job| BatchReportItem {
+                    job_id: job.id,
+                    file_path: job.file_path,
+                    status: job.status,
+                    summary_id: job.summary_id,
+                    error: job.error,
+                })
+                .collect(),
+        }
+    }
+}
+
+pub async fn get_batch_report(database: &SqlitePool, batch_id: Uuid) -> Result<BatchReport> {
+    let jobs = sqlx::query_as::<_, Job>(
+        "SELECT * FROM jobs WHERE batch_id = ? ORDER BY created_at ASC, rowid ASC",
+    )
+    .bind(batch_id)
+    .fetch_all(database)
+    .await
+    .context("Failed to fetch batch jobs from database")?;
+
+    Ok(BatchReport::from_jobs(batch_id, jobs))
+}
+
+/// Expands directories into the files directly inside them. Hidden files are skipped and
+/// nested directories are not traversed.
+pub async fn collect_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+
+    for path in paths {
+        if !tokio::fs::metadata(path)
+            .await
+            .map(|m| m.is_dir())
+            .unwrap_or(false)
+        {
+            files.push(path.clone());
+            continue;
+        }
+
+        let mut entries = tokio::fs::read_dir(path)
+            .await
+            .with_context(|| format!("Failed to read directory {}", path.display()))?;
+        let mut directory_files = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .context("Failed to read directory entry")?
+        {
+            let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+            let is_file = entry
+                .file_type()
+                .await
+                .map(|t| t.is_file())
+                .unwrap_or(false);
+            if is_file && !is_hidden {
+                directory_files.push(entry.path());
+            }
+        }
+
+        directory_files.sort();
+        files.extend(directory_files);
+    }
+
+    Ok(files)
+}
@@ -0,0 +1,62 @@
+use anyhow::Context;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::ErrorCode,
+    features::job::{batch::BatchReport, entities::Job},
+    state::AppState,
+};
+
+#[tauri::command]
+pub async fn list_jobs(state: State<'_, AppState>) -> Result<Vec<Job>, ErrorCode> {
+    let jobs = state
+        .job_manager
+        .get_all()
+        .await
+        .context("Failed to list jobs")?;
+
+    Ok(jobs)
+}
+
+#[tauri::command]
+pub async fn cancel_job(state: State<'_, AppState>, job_id: Uuid) -> Result<bool, ErrorCode> {
+    let cancelled = state
+        .job_manager
+        .cancel(job_id)
+        .await
+        .context("Failed to cancel job")?;
+
+    Ok(cancelled)
+}
+
+#[tauri::command]
+pub async fn retry_job(state: State<'_, AppState>, job_id: Uuid) -> Result<Job, ErrorCode> {
+    let job = state
+        .job_manager
+        .retry(job_id)
+        .await
+        .context("Failed to retry job")?
+        .ok_or_else(|| {
+            ErrorCode::invalid_input(format!(
+                "Job {} can only be retried after it failed or was cancelled",
+                job_id
+            ))
+        })?;
+
+    Ok(job)
+}
+
+#[tauri::command]
+pub async fn get_batch_report(
+    state: State<'_, AppState>,
+    batch_id: Uuid,
+) -> Result<BatchReport, ErrorCode> {
+    let report = state
+        .job_manager
+        .get_batch(batch_id)
+        .await
+        .context("Failed to fetch batch report")?;
+
+    Ok(report)
+}
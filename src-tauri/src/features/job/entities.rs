@@ -0,0 +1,136 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use strum_macros::{Display, EnumString};
+use uuid::Uuid;
+
+use crate::{
+    features::{
+        model::speech_to_text::{SpeechToTextModel, TranscriptionMode, TranscriptionSettings},
+        summarize::{
+            audio::AudioSelection, entities::SourceFiles, language::Language, media::MediaInfo,
+        },
+    },
+    utils::sqlite::impl_sqlite_string_enum,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display, EnumString)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+/// Last pipeline stage whose output has been persisted. A resumed job continues right after it.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Display, EnumString,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "snake_case")]
+pub enum JobStage {
+    Pending,
+    AudioDecoded,
+    Transcribed,
+    Summarized,
+}
+
+impl_sqlite_string_enum!(JobStatus);
+impl_sqlite_string_enum!(JobStage);
+
+/// Settings a summarization job is queued with.
+#[derive(Clone)]
+pub struct JobOptions {
+    pub language: Language,
+    /// Note structure the final summary should follow
+    pub template: Option<String>,
+    pub transcription_mode: TranscriptionMode,
+    /// Also transcribe the recording in its own language when translating
+    pub keep_original_transcript: bool,
+    /// Glossaries used for this run in addition to the global ones
+    pub glossary_ids: Vec<Uuid>,
+    /// Audio streams and channels to transcribe
+    pub audio_selection: AudioSelection,
+    /// Files recorded after the main one, concatenated to it in this order
+    pub appended_files: Vec<String>,
+    /// Range of the combined recording to transcribe, in seconds
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+}
+
+impl JobOptions {
+    pub fn new(language: Language) -> Self {
+        JobOptions {
+            language,
+            template: None,
+            transcription_mode: TranscriptionMode::default(),
+            keep_original_transcript: false,
+            glossary_ids: Vec::new(),
+            audio_selection: AudioSelection::default(),
+            appended_files: Vec::new(),
+            start_time: None,
+            end_time: None,
+        }
+    }
+}
+
+#[derive(Clone, FromRow, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: Uuid,
+    pub batch_id: Option<Uuid>,
+    pub file_path: String,
+    /// Replaced by the detected language once transcription ran for `Language::Auto`
+    pub language: Language,
+    pub language_confidence: Option<f64>,
+    /// Note structure the final summary should follow
+    pub template: Option<String>,
+    pub transcription_mode: TranscriptionMode,
+    pub keep_original_transcript: bool,
+    pub status: JobStatus,
+    pub stage: JobStage,
+    /// Decoded f32le samples cached on disk between the decode and transcription stages
+    #[serde(skip)]
+    pub audio_path: Option<String>,
+    /// Transcribed segments serialized as JSON
+    #[serde(skip)]
+    pub transcript: Option<String>,
+    /// Segments in the spoken language, kept next to a translated transcript
+    #[serde(skip)]
+    pub original_transcript: Option<String>,
+    /// Model and decoding settings the transcript was produced with
+    pub speech_to_text_model: Option<SpeechToTextModel>,
+    pub transcription_settings: Option<TranscriptionSettings>,
+    /// Metadata of the source file, probed before decoding
+    pub media_info: Option<MediaInfo>,
+    /// Missing for jobs queued before streams could be selected
+    pub audio_selection: Option<AudioSelection>,
+    /// Every input file when several are concatenated, with their offsets once decoded
+    pub sources: Option<SourceFiles>,
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+    /// Tracks decoded separately into the cache, serialized as JSON
+    #[serde(skip)]
+    pub audio_tracks: Option<String>,
+    /// Speakers found by diarization serialized as JSON
+    #[serde(skip)]
+    pub speakers: Option<String>,
+    #[serde(skip)]
+    pub summary: Option<String>,
+    pub summary_id: Option<Uuid>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
@@ -1,3 +1,7 @@
 pub mod chat;
+pub mod glossary;
+pub mod job;
 pub mod model;
+pub mod speaker;
 pub mod summarize;
+pub mod watch;
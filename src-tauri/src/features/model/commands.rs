@@ -1,17 +1,19 @@
-use super::speech_to_text::SpeechToTextModel;
+use super::speech_to_text::{SpeechToTextModel, TranscriptionSettings};
 use crate::{
     api::HTTP,
     error::ErrorCode,
-    features::model::text_generation::{gemini::Gemini, Model, Provider},
+    features::model::text_generation::{llama::LocalTextGenerationModel, Model, Provider},
     state::{
         download::{Checksum, FileDownload},
         AppState,
     },
+    utils::tauri::get_settings_store,
 };
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use tauri::{AppHandle, Manager};
+use tracing::warn;
 
 #[derive(Serialize)]
 pub struct GetSpeechToTextModelResult {
@@ -19,10 +21,41 @@ pub struct GetSpeechToTextModelResult {
     pub size: u64,
 }
 
+#[derive(Serialize)]
+pub struct GetLocalTextGenerationModelResult {
+    pub model: LocalTextGenerationModel,
+    #[serde(rename = "displayName")]
+    pub display_name: &'static str,
+    pub size: u64,
+}
+
 #[derive(Debug, Deserialize)]
 struct RepositoryTree {
     pub size: u64,
     pub path: String,
+    pub lfs: Option<RepositoryTreeLfs>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepositoryTreeLfs {
+    /// SHA-256 of the file content
+    pub oid: String,
+}
+
+async fn get_repository_file(repository: &str, path: &str) -> Result<RepositoryTree> {
+    HTTP.get(format!(
+        "https://huggingface.co/api/models/{}/tree/main",
+        repository
+    ))
+    .send()
+    .await
+    .with_context(|| format!("Failed to fetch repository files for {}", repository))?
+    .json::<Vec<RepositoryTree>>()
+    .await
+    .context("Failed to parse repository files response in JSON format")?
+    .into_iter()
+    .find(|file| file.path == path)
+    .with_context(|| format!("File {} not found in repository {}", path, repository))
 }
 
 #[tauri::command]
@@ -84,25 +117,115 @@ pub async fn download_speech_to_text_model(
 
 #[tauri::command]
 pub async fn set_text_generation_api_key(
+    app: AppHandle,
     provider: Provider,
     api_key: String,
+    base_url: Option<String>,
 ) -> Result<bool, ErrorCode> {
-    match provider {
-        Provider::Gemini => {
-            let is_valid = Gemini::set_api_key(api_key)
-                .await
-                .context("Error setting Gemini API key")?;
+    let is_valid = provider
+        .set_api_key(&app, api_key, base_url)
+        .await
+        .with_context(|| format!("Error setting {} API key", provider))?;
+
+    Ok(is_valid)
+}
+
+#[tauri::command]
+pub async fn get_transcription_settings(
+    app: AppHandle,
+) -> Result<TranscriptionSettings, ErrorCode> {
+    let store = get_settings_store(&app).context("Failed to get settings store")?;
+    let settings = TranscriptionSettings::from_store(&store)?;
+
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn set_transcription_settings(
+    app: AppHandle,
+    settings: TranscriptionSettings,
+) -> Result<TranscriptionSettings, ErrorCode> {
+    if let Err(e) = settings.validate() {
+        return Err(ErrorCode::invalid_input(e.to_string()));
+    }
+
+    get_settings_store(&app)
+        .context("Failed to get settings store")?
+        .set(
+            TranscriptionSettings::STORE_KEY,
+            serde_json::to_value(&settings)
+                .context("Failed to serialize transcription settings")?,
+        );
+
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_text_generation_models(app: AppHandle) -> Result<Vec<Model>, ErrorCode> {
+    let mut models = Vec::new();
+
+    for provider in Provider::iter() {
+        // Providers missing their API key or base URL are not configured, so they have no models to offer
+        let Ok(text_generation) = provider.text_generation(&app, String::new()) else {
+            continue;
+        };
 
-            Ok(is_valid)
+        match text_generation.get_models().await {
+            Ok(provider_models) => models.extend(provider_models),
+            Err(e) => {
+                warn!(provider = %provider, error = %e, "Error fetching text generation models")
+            }
         }
     }
+
+    Ok(models)
+}
+
+#[tauri::command]
+pub async fn get_local_text_generation_models(
+) -> Result<Vec<GetLocalTextGenerationModelResult>, ErrorCode> {
+    let mut models = Vec::new();
+
+    for model in LocalTextGenerationModel::iter() {
+        let file = get_repository_file(model.repository(), model.filename()).await?;
+
+        models.push(GetLocalTextGenerationModelResult {
+            model,
+            display_name: model.display_name(),
+            size: file.size,
+        });
+    }
+
+    Ok(models)
 }
 
 #[tauri::command]
-pub async fn get_text_generation_models() -> Result<Vec<Model>, ErrorCode> {
-    let gemini_models = Gemini::get_models()
+pub async fn download_text_generation_model(
+    app: AppHandle,
+    model: LocalTextGenerationModel,
+) -> Result<(), ErrorCode> {
+    let save_path = app
+        .path()
+        .app_local_data_dir()
+        .context("Failed to get app local data directory")?
+        .join("models");
+
+    // GGUF files are stored with Git LFS, whose object ID is the SHA-256 of the content
+    let checksum = get_repository_file(model.repository(), model.filename())
+        .await?
+        .lfs
+        .map(|lfs| Checksum::Sha256(lfs.oid))
+        .context("Repository file has no LFS checksum")?;
+
+    app.state::<AppState>()
+        .download_manager
+        .start(FileDownload::new(
+            model.download_url(),
+            save_path,
+            Some(checksum),
+        ))
         .await
-        .context("Error fetching Gemini models")?;
+        .context("Failed to start download")?;
 
-    Ok(gemini_models)
+    Ok(())
 }
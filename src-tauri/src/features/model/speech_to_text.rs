@@ -1,15 +1,33 @@
-use std::{path::PathBuf, thread};
+use std::{
+    path::Path,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, ensure, Context, Result};
 use serde::{Deserialize, Serialize};
-use strum_macros::EnumIter;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+use tauri::Wry;
+use tauri_plugin_store::Store;
 use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
-use crate::features::summarize::language::Language;
+use crate::{
+    features::summarize::{audio::SAMPLE_RATE, language::Language},
+    utils::sqlite::{impl_sqlite_json, impl_sqlite_string_enum},
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
+/// Whisper detects the language from a single 30 second window.
+const LANGUAGE_DETECTION_SAMPLES: usize = 30 * SAMPLE_RATE;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, Display, EnumString,
+)]
 #[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
 pub enum SpeechToTextModel {
     Tiny,
     Base,
@@ -54,28 +72,291 @@ impl SpeechToTextModel {
     }
 }
 
-#[derive(Clone)]
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Display, EnumString,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "snake_case")]
+pub enum TranscriptionMode {
+    /// Text in the spoken language
+    #[default]
+    Transcribe,
+    /// English text, whatever language is spoken
+    Translate,
+}
+
+impl_sqlite_string_enum!(SpeechToTextModel);
+impl_sqlite_string_enum!(TranscriptionMode);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DecodingStrategy {
+    /// Fastest, picks the most likely token at every step
+    Greedy,
+    /// Slower, keeps several candidate transcriptions and picks the best one
+    #[default]
+    BeamSearch,
+}
+
+/// Whisper decoding options from the settings store. Every summary keeps a copy, so its
+/// transcript can be reproduced later.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TranscriptionSettings {
+    pub strategy: DecodingStrategy,
+    /// Candidates kept by beam search, ignored for greedy decoding
+    pub beam_size: u8,
+    /// Sampling temperature of the first decoding attempt
+    pub temperature: f32,
+    /// Added to the temperature whenever a decoding attempt fails, 0 disables the fallback
+    pub temperature_increment: f32,
+    /// Whisper picks the thread count when unset
+    pub threads: Option<u16>,
+    pub use_gpu: bool,
+    pub gpu_device: u16,
+    /// Segments more likely than this to contain no speech are dropped
+    pub no_speech_threshold: f32,
+    /// Maximum segment length in characters, unlimited when unset
+    pub max_segment_length: Option<u16>,
+}
+
+impl Default for TranscriptionSettings {
+    fn default() -> Self {
+        TranscriptionSettings {
+            strategy: DecodingStrategy::BeamSearch,
+            beam_size: 5,
+            temperature: 0.0,
+            temperature_increment: 0.2,
+            threads: None,
+            use_gpu: true,
+            gpu_device: 0,
+            no_speech_threshold: 0.6,
+            max_segment_length: None,
+        }
+    }
+}
+
+impl_sqlite_json!(TranscriptionSettings);
+
+impl TranscriptionSettings {
+    pub const STORE_KEY: &'static str = "transcription.decoding";
+
+    /// Reads the settings, falling back to the defaults when none were saved.
+    pub fn from_store(store: &Store<Wry>) -> Result<Self> {
+        let settings = store
+            .get(Self::STORE_KEY)
+            .map(serde_json::from_value::<TranscriptionSettings>)
+            .transpose()
+            .context("Failed to parse transcription settings")?
+            .unwrap_or_default();
+        settings.validate()?;
+
+        Ok(settings)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            (1..=16).contains(&self.beam_size),
+            "Beam size must be between 1 and 16"
+        );
+        ensure!(
+            (0.0..=1.0).contains(&self.temperature),
+            "Temperature must be between 0 and 1"
+        );
+        ensure!(
+            (0.0..=1.0).contains(&self.temperature_increment),
+            "Temperature increment must be between 0 and 1"
+        );
+        ensure!(
+            self.threads.is_none_or(|threads| threads > 0),
+            "Thread count must be at least 1"
+        );
+        ensure!(
+            (0.0..=1.0).contains(&self.no_speech_threshold),
+            "No-speech threshold must be between 0 and 1"
+        );
+        ensure!(
+            self.max_segment_length.is_none_or(|length| length > 0),
+            "Maximum segment length must be at least 1"
+        );
+
+        Ok(())
+    }
+
+    fn sampling_strategy(&self) -> SamplingStrategy {
+        match self.strategy {
+            DecodingStrategy::Greedy => SamplingStrategy::Greedy { best_of: 5 },
+            DecodingStrategy::BeamSearch => SamplingStrategy::BeamSearch {
+                beam_size: i32::from(self.beam_size),
+                patience: -1.0,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Segment {
     pub text: String,
     pub start: f64,
     pub end: f64,
+    #[serde(default)]
+    pub words: Vec<Word>,
+    /// Label assigned by diarization, e.g. "Speaker 1"
+    #[serde(default)]
+    pub speaker: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Word {
+    pub text: String,
+    pub start: f64,
+    pub end: f64,
+    /// Lowest probability among the tokens that make up the word
+    pub probability: f32,
+}
+
+struct Token {
+    /// Raw token bytes. Whisper's byte-level tokens can end in the middle of a character.
+    bytes: Vec<u8>,
+    start: f64,
+    end: f64,
+    probability: f32,
+}
+
+/// Whether `c` belongs to a script written without spaces between words.
+fn is_spaceless(c: char) -> bool {
+    matches!(c,
+        '\u{0E00}'..='\u{0EFF}' // Thai, Lao
+        | '\u{3000}'..='\u{30FF}' // CJK punctuation, Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{FF00}'..='\u{FFEF}' // Fullwidth forms
+    )
+}
+
+/// Joins whisper's sub-word tokens into words. A token starting with a space begins a new word,
+/// scripts without spaces end up with one word per token. Bytes of a character split across
+/// tokens are joined before decoding. Special tokens are dropped.
+fn group_words(tokens: impl IntoIterator<Item = Token>) -> Vec<Word> {
+    let mut words: Vec<Token> = Vec::new();
+
+    for token in tokens {
+        if token.bytes.starts_with(b"[_") || token.bytes.starts_with(b"<|") {
+            continue;
+        }
+
+        let continues_word = words.last().is_some_and(|word| {
+            let incomplete =
+                std::str::from_utf8(&word.bytes).is_err_and(|e| e.error_len().is_none());
+            let last_char = String::from_utf8_lossy(&word.bytes).chars().last();
+            let first_char = String::from_utf8_lossy(&token.bytes).chars().next();
+
+            incomplete
+                || !(token.bytes.starts_with(b" ")
+                    || first_char.is_some_and(is_spaceless)
+                    || last_char.is_some_and(is_spaceless))
+        });
+        match words.last_mut() {
+            Some(word) if continues_word => {
+                word.bytes.extend_from_slice(&token.bytes);
+                word.end = token.end;
+                word.probability = word.probability.min(token.probability);
+            }
+            _ => words.push(token),
+        }
+    }
+
+    words
+        .into_iter()
+        .map(|word| Word {
+            text: String::from_utf8_lossy(&word.bytes).trim().to_string(),
+            start: word.start,
+            end: word.end,
+            probability: word.probability,
+        })
+        .filter(|word| !word.text.is_empty())
+        .collect()
+}
+
+#[derive(Clone, Copy)]
+pub struct TranscriptionProgress {
+    /// Share of the audio processed so far, from 0 to 100
+    pub percent: u8,
+    /// Estimated time left, known once some audio has been processed
+    pub eta: Option<Duration>,
+}
+
+/// Loads a whisper model from disk. This is slow for the larger models, so loaded contexts are
+/// shared through the model cache instead of being loaded for every transcription.
+pub fn load_context(model_path: &Path, use_gpu: bool, gpu_device: u16) -> Result<WhisperContext> {
+    let mut params = WhisperContextParameters::default();
+    params.use_gpu = use_gpu;
+    params.gpu_device = i32::from(gpu_device);
+
+    WhisperContext::new_with_params(model_path.to_string_lossy().as_ref(), params)
+        .context("Failed to create Whisper context")
 }
 
 pub struct Whisper {
-    model_path: PathBuf,
+    context: Arc<WhisperContext>,
+    settings: TranscriptionSettings,
 }
 
 impl Whisper {
-    pub fn new(model_path: PathBuf) -> Self {
-        Self { model_path }
+    pub fn new(context: Arc<WhisperContext>, settings: TranscriptionSettings) -> Self {
+        Self { context, settings }
+    }
+
+    /// Detects the spoken language from the start of the audio, choosing among the languages the
+    /// app supports. Returns the language together with the probability whisper assigned to it.
+    pub async fn detect_language(&self, audio_data: &[f32]) -> Result<(Language, f32)> {
+        let ctx = Arc::clone(&self.context);
+        let audio_data = audio_data[..audio_data.len().min(LANGUAGE_DETECTION_SAMPLES)].to_vec();
+        let threads = self.settings.threads.map(usize::from);
+
+        tokio::task::spawn_blocking(move || {
+            let threads = threads
+                .or_else(|| thread::available_parallelism().map(|n| n.get()).ok())
+                .unwrap_or(4);
+            let mut state = ctx
+                .create_state()
+                .context("Failed to create Whisper state")?;
+            state
+                .pcm_to_mel(&audio_data, threads)
+                .context("Failed to compute mel spectrogram")?;
+            let (_, probabilities) = state
+                .lang_detect(0, threads)
+                .context("Failed to detect language")?;
+
+            Language::iter()
+                .filter(|language| !matches!(language, Language::Auto))
+                .filter_map(|language| {
+                    let id = whisper_rs::get_lang_id(language.code())?;
+                    let probability = probabilities.get(usize::try_from(id).ok()?)?;
+                    Some((language, *probability))
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .context("No supported language was detected")
+        })
+        .await
+        .context("Language detection thread panicked")?
     }
 
+    /// Transcribes audio on a dedicated thread. Cancelling the token aborts whisper at its
+    /// next checkpoint, so the thread stops shortly after instead of running to completion.
+    /// `initial_prompt` is fed to whisper as preceding text to steer its spelling. The audio is
+    /// shared, so the caller can keep using it for other passes without a copy.
     pub async fn transcribe(
         &self,
-        audio_data: Vec<f32>,
+        audio_data: Arc<Vec<f32>>,
         language: Language,
+        mode: TranscriptionMode,
+        initial_prompt: Option<String>,
+        cancellation_token: CancellationToken,
+        on_progress: impl Fn(TranscriptionProgress) + Send + 'static,
     ) -> Result<Vec<Segment>> {
-        let model_path = self.model_path.clone();
+        let ctx = Arc::clone(&self.context);
+        let settings = self.settings.clone();
         let (tx, rx) = oneshot::channel();
 
         thread::Builder::new()
@@ -83,36 +364,76 @@ impl Whisper {
             .stack_size(10 * 1024 * 1024) // 10 MB stack size
             .spawn(move || {
                 let result = (|| -> anyhow::Result<Vec<Segment>> {
-                    let ctx = WhisperContext::new_with_params(
-                        model_path.to_string_lossy().as_ref(),
-                        WhisperContextParameters::default(),
-                    )
-                    .context("Failed to create Whisper context")?;
-
-                    let mut params = FullParams::new(SamplingStrategy::BeamSearch {
-                        beam_size: 5,
-                        patience: -1.0,
-                    });
+                    let mut params = FullParams::new(settings.sampling_strategy());
 
                     params.set_print_realtime(false);
                     params.set_print_progress(false);
 
                     params.set_language(Some(language.code()));
+                    params.set_translate(mode == TranscriptionMode::Translate);
+                    params.set_token_timestamps(true);
+                    params.set_temperature(settings.temperature);
+                    params.set_temperature_inc(settings.temperature_increment);
+                    params.set_no_speech_thold(settings.no_speech_threshold);
+                    if let Some(threads) = settings.threads {
+                        params.set_n_threads(i32::from(threads));
+                    }
+                    if let Some(length) = settings.max_segment_length {
+                        params.set_max_len(i32::from(length));
+                        params.set_split_on_word(true);
+                    }
+                    if let Some(prompt) = &initial_prompt {
+                        params.set_initial_prompt(prompt);
+                    }
 
                     let mut state = ctx
                         .create_state()
                         .context("Failed to create Whisper state")?;
 
-                    state
-                        .full(params, &audio_data[..])
-                        .context("Failed to run full transcription")?;
+                    let started_at = Instant::now();
+                    params.set_progress_callback_safe(move |percent: i32| {
+                        let percent = percent.clamp(0, 100) as u8;
+                        let eta = (percent > 0).then(|| {
+                            started_at
+                                .elapsed()
+                                .mul_f64(f64::from(100 - percent) / f64::from(percent))
+                        });
+                        on_progress(TranscriptionProgress { percent, eta });
+                    });
+                    let abort_token = cancellation_token.clone();
+                    params.set_abort_callback_safe(move || abort_token.is_cancelled());
+
+                    if cancellation_token.is_cancelled() {
+                        bail!("Transcription was cancelled");
+                    }
+                    let result = state.full(params, &audio_data[..]);
+                    if cancellation_token.is_cancelled() {
+                        bail!("Transcription was cancelled");
+                    }
+                    result.context("Failed to run full transcription")?;
 
                     let segments = state
                         .as_iter()
-                        .map(|segment| Segment {
-                            text: segment.to_string(),
-                            start: (segment.start_timestamp() as f64) / 100.0,
-                            end: (segment.end_timestamp() as f64) / 100.0,
+                        .map(|segment| {
+                            let tokens = (0..segment.n_tokens())
+                                .filter_map(|i| segment.get_token(i))
+                                .filter_map(|token| {
+                                    let data = token.token_data();
+                                    Some(Token {
+                                        bytes: token.to_bytes().ok()?.to_vec(),
+                                        start: (data.t0 as f64) / 100.0,
+                                        end: (data.t1 as f64) / 100.0,
+                                        probability: data.p,
+                                    })
+                                });
+
+                            Segment {
+                                text: segment.to_string(),
+                                start: (segment.start_timestamp() as f64) / 100.0,
+                                end: (segment.end_timestamp() as f64) / 100.0,
+                                words: group_words(tokens),
+                                speaker: None,
+                            }
                         })
                         .collect::<Vec<Segment>>();
 
@@ -132,6 +453,79 @@ impl Whisper {
 mod tests {
     use super::*;
     use crate::features::summarize::audio::load_f32le_audio;
+    use std::path::PathBuf;
+
+    fn token(text: &str, start: f64, probability: f32) -> Token {
+        Token {
+            bytes: text.as_bytes().to_vec(),
+            start,
+            end: start + 0.1,
+            probability,
+        }
+    }
+
+    #[test]
+    fn test_group_words() {
+        let tokens = vec![
+            token("[_BEG_]", 0.0, 1.0),
+            token(" Hel", 0.0, 0.9),
+            token("lo", 0.1, 0.4),
+            token(" world", 0.2, 0.8),
+            token("<|endoftext|>", 0.3, 1.0),
+        ];
+
+        let words = group_words(tokens);
+
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].text, "Hello");
+        assert_eq!(words[0].end, 0.2);
+        assert_eq!(words[0].probability, 0.4);
+        assert_eq!(words[1].text, "world");
+    }
+
+    #[test]
+    fn test_group_words_split_character() {
+        // "日" is split across two tokens, like whisper's byte-level tokens often do
+        let day = "日".as_bytes();
+        let tokens = vec![
+            Token {
+                bytes: day[..2].to_vec(),
+                ..token("", 0.0, 0.9)
+            },
+            Token {
+                bytes: day[2..].to_vec(),
+                ..token("", 0.1, 0.7)
+            },
+            token("本", 0.2, 0.8),
+            token("語", 0.3, 0.8),
+        ];
+
+        let words = group_words(tokens);
+
+        let texts = words
+            .iter()
+            .map(|word| word.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec!["日", "本", "語"]);
+        assert_eq!(words[0].end, 0.2);
+        assert_eq!(words[0].probability, 0.7);
+    }
+
+    #[test]
+    fn test_transcription_settings() {
+        let settings: TranscriptionSettings =
+            serde_json::from_str(r#"{"strategy":"greedy","threads":8}"#).unwrap();
+        assert_eq!(settings.strategy, DecodingStrategy::Greedy);
+        assert_eq!(settings.threads, Some(8));
+        assert_eq!(settings.beam_size, 5);
+        assert!(settings.validate().is_ok());
+
+        let invalid = TranscriptionSettings {
+            beam_size: 0,
+            ..TranscriptionSettings::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
 
     #[tokio::test]
     async fn test_whisper_transcription() -> Result<()> {
@@ -141,11 +535,26 @@ mod tests {
             .join("video")
             .join("stt.mp4");
         let audio_data = load_f32le_audio(&audio_path).await?;
-        let whisper = Whisper::new(PathBuf::from(
-            "D:\\Rust\\ml-research\\models\\ggml-medium-q8_0.bin",
-        ));
+        let settings = TranscriptionSettings::default();
+        let whisper = Whisper::new(
+            Arc::new(load_context(
+                &PathBuf::from("D:\\Rust\\ml-research\\models\\ggml-medium-q8_0.bin"),
+                settings.use_gpu,
+                settings.gpu_device,
+            )?),
+            settings,
+        );
 
-        let segments = whisper.transcribe(audio_data, Language::EnUs).await?;
+        let segments = whisper
+            .transcribe(
+                Arc::new(audio_data),
+                Language::English,
+                TranscriptionMode::Transcribe,
+                None,
+                CancellationToken::new(),
+                |_| {},
+            )
+            .await?;
 
         for segment in segments {
             println!("{}", segment.text);
@@ -0,0 +1,311 @@
+use anyhow::{Context, Result};
+use futures_util::{stream::BoxStream, StreamExt};
+use reqwest::RequestBuilder;
+use reqwest_eventsource::{Event, EventSource};
+use serde::{Deserialize, Serialize};
+use tokio::select;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use super::{
+    Message as TextGenerationMessage, Model as TextGenerationModel, Provider, Role, TextGeneration,
+};
+use crate::api::HTTP;
+
+const BASE_URL: &str = "https://api.anthropic.com/v1";
+const API_VERSION: &str = "2023-06-01";
+const MAX_TOKENS: u32 = 8192;
+/// Output limit of Claude 3 models, also used for models not known to allow more.
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+/// Model families that accept `MAX_TOKENS` output tokens
+const MAX_TOKENS_MODELS: [&str; 5] = [
+    "claude-3-5-",
+    "claude-3-7-",
+    "claude-haiku-4",
+    "claude-sonnet-4",
+    "claude-opus-4",
+];
+
+pub struct Anthropic {
+    api_key: String,
+    model_id: String,
+}
+
+#[derive(Deserialize)]
+struct Model {
+    id: String,
+    display_name: String,
+}
+
+#[derive(Deserialize)]
+struct GetModelsResponse {
+    data: Vec<Model>,
+    #[serde(default)]
+    has_more: bool,
+    last_id: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Message {
+    role: String,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct MessageRequest {
+    model: String,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<Message>,
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct ContentBlock {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct MessageResponse {
+    content: Vec<ContentBlock>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    ContentBlockDelta {
+        delta: ContentBlockDelta,
+    },
+    MessageStop,
+    Error {
+        error: StreamError,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct ContentBlockDelta {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StreamError {
+    message: String,
+}
+
+impl Anthropic {
+    pub fn new_with_api_key(api_key: String, model_id: String) -> Self {
+        Anthropic { api_key, model_id }
+    }
+
+    fn with_headers(&self, request: RequestBuilder) -> RequestBuilder {
+        request
+            .header("x-api-key", self.api_key.clone())
+            .header("anthropic-version", API_VERSION)
+    }
+
+    fn message_request(&self, messages: &[TextGenerationMessage], stream: bool) -> MessageRequest {
+        // The Messages API takes system prompts as a top-level field instead of a message role
+        let system = messages
+            .iter()
+            .filter(|m| matches!(m.role, Role::System))
+            .map(|m| m.text.clone())
+            .collect::<Vec<_>>();
+
+        MessageRequest {
+            model: self.model_id.clone(),
+            max_tokens: max_tokens(&self.model_id),
+            system: Some(system.join("\n\n")).filter(|s| !s.is_empty()),
+            messages: messages
+                .iter()
+                .filter(|m| !matches!(m.role, Role::System))
+                .map(|m| Message {
+                    role: m.role.to_string(),
+                    content: m.text.clone(),
+                })
+                .collect(),
+            stream,
+        }
+    }
+}
+
+fn max_tokens(model_id: &str) -> u32 {
+    if MAX_TOKENS_MODELS
+        .iter()
+        .any(|prefix| model_id.starts_with(prefix))
+    {
+        MAX_TOKENS
+    } else {
+        DEFAULT_MAX_TOKENS
+    }
+}
+
+#[async_trait::async_trait]
+impl TextGeneration for Anthropic {
+    fn get_provider(&self) -> Provider {
+        Provider::Anthropic
+    }
+
+    fn context_window(&self) -> usize {
+        200_000
+    }
+
+    async fn get_models(&self) -> Result<Vec<TextGenerationModel>> {
+        let mut models = Vec::new();
+        let mut after_id = None;
+        loop {
+            let mut request = HTTP
+                .get(format!("{}/models", BASE_URL))
+                .query(&[("limit", "1000")]);
+            if let Some(after_id) = &after_id {
+                request = request.query(&[("after_id", after_id)]);
+            }
+            let response = self
+                .with_headers(request)
+                .send()
+                .await
+                .context("Failed to fetch models")?;
+
+            if let Some(e) = response.error_for_status_ref().err() {
+                let text = response.text().await.unwrap_or_default();
+                debug!("Anthropic API error response: {}", text);
+                return Err(anyhow::Error::new(e).context("Unauthorized: Invalid API Key"));
+            }
+
+            let response = response
+                .json::<GetModelsResponse>()
+                .await
+                .context("Failed to parse models response in JSON format")?;
+
+            models.extend(response.data.into_iter().map(|model| TextGenerationModel {
+                id: model.id,
+                name: model.display_name,
+                provider: Provider::Anthropic,
+            }));
+
+            match response.last_id {
+                Some(last_id) if response.has_more => after_id = Some(last_id),
+                _ => break,
+            }
+        }
+
+        Ok(models)
+    }
+
+    async fn generate_text(
+        &self,
+        mut messages: Vec<TextGenerationMessage>,
+    ) -> Result<Vec<TextGenerationMessage>> {
+        let response = self
+            .with_headers(HTTP.post(format!("{}/messages", BASE_URL)))
+            .json(&self.message_request(&messages, false))
+            .send()
+            .await
+            .context("Failed to send text generation request")?
+            .error_for_status()
+            .context("Text generation request failed")?
+            .json::<MessageResponse>()
+            .await
+            .context("Failed to read text generation response")?;
+
+        let text = response
+            .content
+            .into_iter()
+            .filter(|block| block.kind == "text")
+            .map(|block| block.text)
+            .collect::<Vec<_>>()
+            .join("");
+        messages.push(TextGenerationMessage {
+            role: Role::Assistant,
+            text,
+        });
+
+        Ok(messages)
+    }
+
+    fn generate_text_stream(
+        &self,
+        messages: Vec<TextGenerationMessage>,
+        cancellation_token: CancellationToken,
+    ) -> BoxStream<'_, Result<String>> {
+        let request = self
+            .with_headers(HTTP.post(format!("{}/messages", BASE_URL)))
+            .json(&self.message_request(&messages, true));
+
+        Box::pin(async_stream::try_stream! {
+            let mut es = EventSource::new(request)
+                .context("Failed to create event source")?;
+
+            loop {
+                let next_step = select! {
+                    _ = cancellation_token.cancelled() => {
+                        None
+                    }
+                    event = es.next() => {
+                        Some(event)
+                    }
+                };
+
+                match next_step {
+                    None => {
+                        debug!("Stream cancelled by token");
+                        es.close();
+                        break;
+                    }
+
+                    Some(None) => {
+                        debug!("Stream ended naturally");
+                        break;
+                    }
+
+                    Some(Some(Err(e))) => {
+                        es.close();
+                        Err(anyhow::anyhow!("Stream connection error: {}", e))?;
+                    }
+
+                    Some(Some(Ok(event))) => {
+                        match event {
+                            Event::Open => {
+                                debug!("Connection opened");
+                                continue;
+                            }
+                            Event::Message(message) => {
+                                debug!("Received message data: {}", &message.data);
+
+                                let event = match serde_json::from_str::<StreamEvent>(&message.data) {
+                                    Ok(e) => e,
+                                    Err(e) => {
+                                        Err(anyhow::anyhow!("Failed to parse event: {}", e))?
+                                    }
+                                };
+
+                                match event {
+                                    StreamEvent::ContentBlockDelta { delta } => {
+                                        if let Some(text) = delta.text {
+                                            yield text;
+                                        }
+                                    }
+                                    StreamEvent::MessageStop => {
+                                        es.close();
+                                        break;
+                                    }
+                                    StreamEvent::Error { error } => {
+                                        es.close();
+                                        Err(anyhow::anyhow!("Anthropic stream error: {}", error.message))?;
+                                    }
+                                    StreamEvent::Other => continue,
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
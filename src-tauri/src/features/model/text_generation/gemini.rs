@@ -0,0 +1,231 @@
+use anyhow::{Context, Result};
+use futures_util::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use super::{openai, Message, Model as TextGenerationModel, Provider, Role, TextGeneration};
+use crate::api::HTTP;
+
+impl From<Content> for Message {
+    fn from(content: Content) -> Self {
+        let role = match content.role.as_str() {
+            "system" => Role::System,
+            "user" => Role::User,
+            "model" => Role::Assistant,
+            _ => Role::User,
+        };
+        let text = content
+            .parts
+            .into_iter()
+            .map(|p| p.text)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Message { role, text }
+    }
+}
+
+pub struct Gemini {
+    api_key: String,
+    model_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Model {
+    name: String,
+    display_name: String,
+    #[serde(default)]
+    supported_generation_methods: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct GetModelsResponse {
+    models: Vec<Model>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct Content {
+    pub parts: Vec<Part>,
+    pub role: String,
+}
+
+impl From<Message> for Content {
+    fn from(message: Message) -> Self {
+        Content {
+            parts: vec![Part { text: message.text }],
+            role: match message.role {
+                Role::User => "user".to_string(),
+                Role::Assistant => "model".to_string(),
+                Role::System => "system".to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct Part {
+    pub text: String,
+}
+
+#[derive(Deserialize)]
+struct Candidate {
+    pub content: Content,
+}
+
+#[derive(Serialize)]
+struct MessageRequest {
+    pub contents: Vec<Content>,
+    #[serde(rename = "systemInstruction", skip_serializing_if = "Option::is_none")]
+    pub system_instruction: Option<Content>,
+}
+
+#[derive(Deserialize)]
+struct MessageResponse {
+    pub candidates: Vec<Candidate>,
+}
+
+/// Input token limit of the Gemini models known to accept a million tokens. Other models,
+/// including older and future ones, get a conservative limit so a long transcript is
+/// split into parts instead of being rejected.
+fn get_context_window(model_id: &str) -> usize {
+    const LONG_CONTEXT_PREFIXES: [&str; 3] = ["gemini-1.5-", "gemini-2.0-", "gemini-2.5-"];
+
+    if LONG_CONTEXT_PREFIXES
+        .iter()
+        .any(|prefix| model_id.starts_with(prefix))
+    {
+        1_048_576
+    } else {
+        32_768
+    }
+}
+
+impl Gemini {
+    pub fn new_with_api_key(api_key: String, model_id: String) -> Self {
+        Gemini { api_key, model_id }
+    }
+}
+
+#[async_trait::async_trait]
+impl TextGeneration for Gemini {
+    fn get_provider(&self) -> Provider {
+        Provider::Gemini
+    }
+
+    fn context_window(&self) -> usize {
+        get_context_window(&self.model_id)
+    }
+
+    async fn get_models(&self) -> Result<Vec<TextGenerationModel>> {
+        let response = HTTP
+            .get("https://generativelanguage.googleapis.com/v1beta/models")
+            .header("x-goog-api-key", self.api_key.clone())
+            .send()
+            .await
+            .context("Failed to fetch models")?;
+
+        if let Some(e) = response.error_for_status_ref().err() {
+            let text = response.text().await.unwrap_or_default();
+            debug!("Gemini API error response: {}", text);
+            return Err(anyhow::Error::new(e).context("Unauthorized: Invalid API Key"));
+        }
+
+        let response = response
+            .json::<GetModelsResponse>()
+            .await
+            .context("Failed to parse models response in JSON format")?;
+
+        let models = response
+            .models
+            .into_iter()
+            .filter(|model| {
+                model
+                    .supported_generation_methods
+                    .contains(&"generateContent".to_string())
+            })
+            .filter(|model| model.display_name.contains("Gemini"))
+            .map(|model| TextGenerationModel {
+                id: model.name.split("/").last().unwrap_or_default().to_string(),
+                name: model.display_name,
+                provider: Provider::Gemini,
+            })
+            .collect();
+
+        Ok(models)
+    }
+
+    async fn generate_text(&self, mut messages: Vec<Message>) -> Result<Vec<Message>> {
+        let system_instruction = if messages
+            .first()
+            .map_or(false, |m| matches!(m.role, Role::System))
+        {
+            Some(Content {
+                parts: vec![Part {
+                    text: messages.first().unwrap().text.clone(),
+                }],
+                role: "system".to_string(),
+            })
+        } else {
+            None
+        };
+        let contents: Vec<Content> = messages
+            .iter()
+            .filter(|m| !matches!(m.role, Role::System))
+            .map(|m| Content {
+                parts: vec![Part {
+                    text: m.text.clone(),
+                }],
+                role: match m.role {
+                    Role::User => "user".to_string(),
+                    Role::Assistant => "model".to_string(),
+                    Role::System => "system".to_string(),
+                },
+            })
+            .collect();
+        let request_body = MessageRequest {
+            contents,
+            system_instruction,
+        };
+
+        let response = HTTP
+            .post(&format!(
+                "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent",
+                self.model_id
+            ))
+            .header("x-goog-api-key", self.api_key.clone())
+            .json(&request_body)
+            .send()
+            .await
+            .context("Failed to send text generation request")?
+            .error_for_status()
+            .context("Text generation request failed")?
+            .json::<MessageResponse>()
+            .await
+            .context("Failed to read text generation response")?;
+
+        if let Some(candidate) = response.candidates.first() {
+            messages.push(Message::from(candidate.content.clone()));
+        }
+
+        Ok(messages)
+    }
+
+    fn generate_text_stream(
+        &self,
+        messages: Vec<Message>,
+        cancellation_token: CancellationToken,
+    ) -> BoxStream<'_, Result<String>> {
+        let request = HTTP
+            .post("https://generativelanguage.googleapis.com/v1beta/openai/chat/completions")
+            .bearer_auth(&self.api_key)
+            .json(&openai::ChatCompletionRequest {
+                model: self.model_id.clone(),
+                messages: messages.into_iter().map(openai::Message::from).collect(),
+                stream: true,
+            });
+
+        openai::stream_chat_completion(request, cancellation_token)
+    }
+}
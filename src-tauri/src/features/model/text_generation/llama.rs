@@ -0,0 +1,380 @@
+use std::{
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Once, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use futures_util::stream::BoxStream;
+use llama_cpp_2::{
+    context::params::LlamaContextParams,
+    llama_backend::LlamaBackend,
+    llama_batch::LlamaBatch,
+    model::{params::LlamaModelParams, AddBos, LlamaChatMessage, LlamaModel, Special},
+    sampling::LlamaSampler,
+};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+use super::{
+    Message as TextGenerationMessage, Model as TextGenerationModel, Provider, Role, TextGeneration,
+};
+
+const CONTEXT_SIZE: u32 = 8192;
+/// Models that have not been used for this long are unloaded.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+// llama.cpp refuses to initialize its backend twice, so it is shared by every inference thread
+static LLAMA_BACKEND: OnceCell<LlamaBackend> = OnceCell::new();
+
+fn get_backend() -> Result<&'static LlamaBackend> {
+    LLAMA_BACKEND
+        .get_or_try_init(LlamaBackend::init)
+        .context("Failed to initialize llama.cpp backend")
+}
+
+struct CachedModel {
+    path: PathBuf,
+    model: Arc<LlamaModel>,
+    last_used: Instant,
+}
+
+// Loading a GGUF file takes several seconds, so the last used model stays loaded between
+// requests. Only one is kept at a time, each takes gigabytes of memory.
+static LLAMA_MODEL: Mutex<Option<CachedModel>> = Mutex::new(None);
+static SWEEP: Once = Once::new();
+
+/// Returns the loaded model at `model_path`, loading it from disk on a cache miss.
+fn get_model(backend: &LlamaBackend, model_path: &Path) -> Result<Arc<LlamaModel>> {
+    let mut cached = LLAMA_MODEL.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(cached) = cached.as_mut().filter(|cached| cached.path == model_path) {
+        cached.last_used = Instant::now();
+        return Ok(Arc::clone(&cached.model));
+    }
+
+    *cached = None;
+    info!(path=%model_path.display(), "Loading GGUF model");
+    let model = Arc::new(
+        LlamaModel::load_from_file(backend, model_path, &LlamaModelParams::default())
+            .context("Failed to load GGUF model")?,
+    );
+    *cached = Some(CachedModel {
+        path: model_path.to_path_buf(),
+        model: Arc::clone(&model),
+        last_used: Instant::now(),
+    });
+
+    SWEEP.call_once(|| {
+        let _ = thread::Builder::new()
+            .name("llama-model-sweep".to_string())
+            .spawn(|| loop {
+                thread::sleep(SWEEP_INTERVAL);
+                let mut cached = LLAMA_MODEL.lock().unwrap_or_else(PoisonError::into_inner);
+                let Some(model) = cached.as_mut() else {
+                    continue;
+                };
+                // A model still generating text counts as used
+                if Arc::strong_count(&model.model) > 1 {
+                    model.last_used = Instant::now();
+                } else if model.last_used.elapsed() >= IDLE_TIMEOUT {
+                    info!(path=%model.path.display(), "Unloading GGUF model");
+                    *cached = None;
+                }
+            });
+    });
+
+    Ok(model)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
+pub enum LocalTextGenerationModel {
+    #[serde(rename = "gemma-2-2b")]
+    Gemma2,
+    #[serde(rename = "llama-3.2-3b")]
+    Llama32,
+    #[serde(rename = "qwen-2.5-3b")]
+    Qwen25,
+    #[serde(rename = "qwen-2.5-7b")]
+    Qwen25Large,
+}
+
+impl LocalTextGenerationModel {
+    pub fn id(&self) -> &'static str {
+        match self {
+            LocalTextGenerationModel::Gemma2 => "gemma-2-2b",
+            LocalTextGenerationModel::Llama32 => "llama-3.2-3b",
+            LocalTextGenerationModel::Qwen25 => "qwen-2.5-3b",
+            LocalTextGenerationModel::Qwen25Large => "qwen-2.5-7b",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::iter().find(|model| model.id() == id)
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            LocalTextGenerationModel::Gemma2 => "Gemma 2 2B Instruct",
+            LocalTextGenerationModel::Llama32 => "Llama 3.2 3B Instruct",
+            LocalTextGenerationModel::Qwen25 => "Qwen 2.5 3B Instruct",
+            LocalTextGenerationModel::Qwen25Large => "Qwen 2.5 7B Instruct",
+        }
+    }
+
+    /// Hugging Face repository hosting the GGUF file.
+    pub fn repository(&self) -> &'static str {
+        match self {
+            LocalTextGenerationModel::Gemma2 => "bartowski/gemma-2-2b-it-GGUF",
+            LocalTextGenerationModel::Llama32 => "bartowski/Llama-3.2-3B-Instruct-GGUF",
+            LocalTextGenerationModel::Qwen25 => "Qwen/Qwen2.5-3B-Instruct-GGUF",
+            LocalTextGenerationModel::Qwen25Large => "bartowski/Qwen2.5-7B-Instruct-GGUF",
+        }
+    }
+
+    pub fn filename(&self) -> &'static str {
+        match self {
+            LocalTextGenerationModel::Gemma2 => "gemma-2-2b-it-Q4_K_M.gguf",
+            LocalTextGenerationModel::Llama32 => "Llama-3.2-3B-Instruct-Q4_K_M.gguf",
+            LocalTextGenerationModel::Qwen25 => "qwen2.5-3b-instruct-q4_k_m.gguf",
+            LocalTextGenerationModel::Qwen25Large => "Qwen2.5-7B-Instruct-Q4_K_M.gguf",
+        }
+    }
+
+    pub fn download_url(&self) -> String {
+        format!(
+            "https://huggingface.co/{}/resolve/main/{}",
+            self.repository(),
+            self.filename()
+        )
+    }
+}
+
+/// Runs GGUF models in-process through llama.cpp, the same way whisper.cpp is used for transcription.
+pub struct LlamaCpp {
+    models_dir: PathBuf,
+    model_id: String,
+}
+
+impl LlamaCpp {
+    pub fn new(models_dir: PathBuf, model_id: String) -> Self {
+        LlamaCpp {
+            models_dir,
+            model_id,
+        }
+    }
+
+    fn model_path(&self) -> Result<PathBuf> {
+        let model = LocalTextGenerationModel::from_id(&self.model_id)
+            .with_context(|| format!("Unknown local text generation model: {}", self.model_id))?;
+        let model_path = self.models_dir.join(model.filename());
+
+        if !model_path.exists() {
+            return Err(anyhow::anyhow!(
+                "Local text generation model {} has not been downloaded",
+                self.model_id
+            ));
+        }
+
+        Ok(model_path)
+    }
+
+    /// Spawns a dedicated inference thread which sends every generated piece through `sender`.
+    fn spawn_inference(
+        &self,
+        messages: Vec<TextGenerationMessage>,
+        cancellation_token: CancellationToken,
+        sender: mpsc::UnboundedSender<String>,
+    ) -> Result<oneshot::Receiver<Result<()>>> {
+        let model_path = self.model_path()?;
+        let (tx, rx) = oneshot::channel();
+
+        thread::Builder::new()
+            .name("llama-inference".to_string())
+            .stack_size(10 * 1024 * 1024) // 10 MB stack size
+            .spawn(move || {
+                let result = run_inference(&model_path, messages, &cancellation_token, |piece| {
+                    sender.send(piece).is_ok()
+                });
+
+                let _ = tx.send(result);
+            })
+            .context("Failed to spawn inference thread")?;
+
+        Ok(rx)
+    }
+}
+
+fn run_inference(
+    model_path: &Path,
+    messages: Vec<TextGenerationMessage>,
+    cancellation_token: &CancellationToken,
+    mut on_piece: impl FnMut(String) -> bool,
+) -> Result<()> {
+    let backend = get_backend()?;
+    let model = get_model(backend, model_path)?;
+
+    let chat = messages
+        .into_iter()
+        .map(|m| LlamaChatMessage::new(m.role.to_string(), m.text))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to build chat messages")?;
+    let template = model
+        .chat_template(None)
+        .context("Model does not provide a chat template")?;
+    let prompt = model
+        .apply_chat_template(&template, &chat, true)
+        .context("Failed to apply chat template")?;
+
+    let tokens = model
+        .str_to_token(&prompt, AddBos::Always)
+        .context("Failed to tokenize prompt")?;
+    if tokens.len() >= CONTEXT_SIZE as usize {
+        return Err(anyhow::anyhow!(
+            "Prompt is {} tokens long, which exceeds the {} token context window",
+            tokens.len(),
+            CONTEXT_SIZE
+        ));
+    }
+
+    let context_params = LlamaContextParams::default()
+        .with_n_ctx(NonZeroU32::new(CONTEXT_SIZE))
+        .with_n_batch(CONTEXT_SIZE);
+    let mut context = model
+        .new_context(backend, context_params)
+        .context("Failed to create llama.cpp context")?;
+
+    let mut batch = LlamaBatch::new(CONTEXT_SIZE as usize, 1);
+    let last_index = tokens.len() as i32 - 1;
+    for (i, token) in (0_i32..).zip(tokens) {
+        batch
+            .add(token, i, &[0], i == last_index)
+            .context("Failed to add prompt token to batch")?;
+    }
+    context
+        .decode(&mut batch)
+        .context("Failed to decode prompt")?;
+
+    let mut sampler = LlamaSampler::chain_simple([
+        LlamaSampler::temp(0.7),
+        LlamaSampler::top_p(0.9, 1),
+        LlamaSampler::dist(1234),
+    ]);
+    // A token can end in the middle of a multi-byte character, so hold bytes until they are valid UTF-8
+    let mut pending = Vec::new();
+    let mut position = batch.n_tokens();
+
+    while position < CONTEXT_SIZE as i32 {
+        if cancellation_token.is_cancelled() {
+            break;
+        }
+
+        let token = sampler.sample(&context, batch.n_tokens() - 1);
+        sampler.accept(token);
+
+        if model.is_eog_token(token) {
+            break;
+        }
+
+        pending.extend(
+            model
+                .token_to_bytes(token, Special::Tokenize)
+                .context("Failed to detokenize generated token")?,
+        );
+        let valid_up_to = match std::str::from_utf8(&pending) {
+            Ok(text) => text.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        if valid_up_to > 0 {
+            let piece = String::from_utf8_lossy(&pending[..valid_up_to]).into_owned();
+            pending.drain(..valid_up_to);
+
+            if !on_piece(piece) {
+                break;
+            }
+        }
+
+        batch.clear();
+        batch
+            .add(token, position, &[0], true)
+            .context("Failed to add generated token to batch")?;
+        position += 1;
+
+        context
+            .decode(&mut batch)
+            .context("Failed to decode generated token")?;
+    }
+
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl TextGeneration for LlamaCpp {
+    fn get_provider(&self) -> Provider {
+        Provider::LlamaCpp
+    }
+
+    fn context_window(&self) -> usize {
+        CONTEXT_SIZE as usize
+    }
+
+    async fn get_models(&self) -> Result<Vec<TextGenerationModel>> {
+        let models = LocalTextGenerationModel::iter()
+            .filter(|model| self.models_dir.join(model.filename()).exists())
+            .map(|model| TextGenerationModel {
+                id: model.id().to_string(),
+                name: model.display_name().to_string(),
+                provider: Provider::LlamaCpp,
+            })
+            .collect();
+
+        Ok(models)
+    }
+
+    async fn generate_text(
+        &self,
+        mut messages: Vec<TextGenerationMessage>,
+    ) -> Result<Vec<TextGenerationMessage>> {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let done = self.spawn_inference(messages.clone(), CancellationToken::new(), sender)?;
+
+        let mut text = String::new();
+        while let Some(piece) = receiver.recv().await {
+            text.push_str(&piece);
+        }
+        done.await.context("Inference thread panicked")??;
+
+        messages.push(TextGenerationMessage {
+            role: Role::Assistant,
+            text,
+        });
+
+        Ok(messages)
+    }
+
+    fn generate_text_stream(
+        &self,
+        messages: Vec<TextGenerationMessage>,
+        cancellation_token: CancellationToken,
+    ) -> BoxStream<'_, Result<String>> {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let done = self.spawn_inference(messages, cancellation_token, sender);
+
+        Box::pin(async_stream::try_stream! {
+            let done = done?;
+
+            while let Some(piece) = receiver.recv().await {
+                yield piece;
+            }
+
+            done.await.context("Inference thread panicked")??;
+        })
+    }
+}
@@ -0,0 +1,384 @@
+pub mod anthropic;
+pub mod gemini;
+pub mod llama;
+pub mod ollama;
+pub mod openai;
+
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use anyhow::{Context, Result};
+use futures_util::stream::BoxStream;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteArgumentValue, Decode, Encode, Sqlite, Type};
+use strum_macros::{Display, EnumIter};
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    features::model::text_generation::{
+        anthropic::Anthropic, gemini::Gemini, llama::LlamaCpp, ollama::Ollama,
+        openai::OpenAiCompatible,
+    },
+    security::secret_manager::SecretManager,
+    utils::tauri::get_settings_store,
+};
+
+/// Conservative context window for models whose limit is not known up front.
+pub const DEFAULT_CONTEXT_WINDOW: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Display, Serialize, EnumIter)]
+#[serde(rename_all = "kebab-case")]
+pub enum Provider {
+    Gemini,
+    #[serde(rename = "openai-compatible")]
+    OpenAiCompatible,
+    Ollama,
+    LlamaCpp,
+    Anthropic,
+}
+
+/// Connection details needed to build a [`TextGeneration`] client for a provider.
+#[derive(Clone, Default)]
+pub struct ProviderConfig {
+    pub api_key: Option<String>,
+    pub base_url: Option<String>,
+    pub models_dir: Option<PathBuf>,
+}
+
+impl Provider {
+    fn api_key_name(&self) -> String {
+        format!("{}_API_KEY", self.to_string().to_uppercase())
+    }
+
+    fn base_url_setting_key(&self) -> Option<&'static str> {
+        match self {
+            Provider::Gemini => None,
+            Provider::OpenAiCompatible => Some("model.textGeneration.openAiCompatible.baseUrl"),
+            Provider::Ollama => Some("model.textGeneration.ollama.baseUrl"),
+            Provider::LlamaCpp => None,
+            Provider::Anthropic => None,
+        }
+    }
+
+    pub fn get_api_key(&self) -> Result<String> {
+        let api_key = SecretManager::get(&self.api_key_name())
+            .context("Failed to retrieve API key from secure storage")?;
+
+        Ok(api_key)
+    }
+
+    pub fn load_config(&self, app: &AppHandle) -> Result<ProviderConfig> {
+        let store = get_settings_store(app).context("Failed to get settings store")?;
+        let base_url = match self.base_url_setting_key() {
+            Some(key) => store
+                .get(key)
+                .map(serde_json::from_value::<String>)
+                .transpose()
+                .context("Failed to parse base URL from settings")?,
+            None => None,
+        };
+
+        let models_dir = app
+            .path()
+            .app_local_data_dir()
+            .context("Could not determine app local data directory")?
+            .join("models");
+
+        Ok(ProviderConfig {
+            api_key: self.get_api_key().ok(),
+            base_url,
+            models_dir: Some(models_dir),
+        })
+    }
+
+    /// Validates the configuration against the provider and persists it when it is accepted.
+    /// The API key goes to secure storage, the base URL to the settings store.
+    pub async fn set_api_key(
+        &self,
+        app: &AppHandle,
+        api_key: String,
+        base_url: Option<String>,
+    ) -> Result<bool> {
+        let api_key = Some(api_key).filter(|key| !key.is_empty());
+        let config = ProviderConfig {
+            api_key: api_key.clone(),
+            base_url: base_url.clone(),
+            ..self.load_config(app)?
+        };
+        let is_valid = self
+            .text_generation_with_config(config, String::new())?
+            .validate_api_key()
+            .await?;
+
+        if !is_valid {
+            return Ok(false);
+        }
+
+        match api_key {
+            Some(api_key) => SecretManager::set(&self.api_key_name(), &api_key)
+                .context("Failed to store API key securely")?,
+            None => {
+                // Not every provider requires a key, so a missing one is not an error
+                let _ = SecretManager::delete(&self.api_key_name());
+            }
+        }
+        if let (Some(key), Some(base_url)) = (self.base_url_setting_key(), base_url) {
+            get_settings_store(app)
+                .context("Failed to get settings store")?
+                .set(key, serde_json::Value::String(base_url));
+        }
+
+        Ok(true)
+    }
+
+    pub fn text_generation_with_config(
+        &self,
+        config: ProviderConfig,
+        model_id: String,
+    ) -> Result<Box<dyn TextGeneration>> {
+        match self {
+            Provider::Gemini => {
+                let api_key = config.api_key.context("Gemini requires an API key")?;
+
+                Ok(Box::new(Gemini::new_with_api_key(api_key, model_id)))
+            }
+            Provider::OpenAiCompatible => {
+                let base_url = config
+                    .base_url
+                    .context("OpenAI-compatible provider requires a base URL")?;
+
+                Ok(Box::new(OpenAiCompatible::new(
+                    base_url,
+                    config.api_key,
+                    model_id,
+                )))
+            }
+            Provider::Ollama => {
+                let base_url = config
+                    .base_url
+                    .unwrap_or_else(|| ollama::DEFAULT_BASE_URL.to_string());
+
+                Ok(Box::new(Ollama::new(base_url, model_id)))
+            }
+            Provider::LlamaCpp => {
+                let models_dir = config
+                    .models_dir
+                    .context("Local text generation requires a models directory")?;
+
+                Ok(Box::new(LlamaCpp::new(models_dir, model_id)))
+            }
+            Provider::Anthropic => {
+                let api_key = config.api_key.context("Anthropic requires an API key")?;
+
+                Ok(Box::new(Anthropic::new_with_api_key(api_key, model_id)))
+            }
+        }
+    }
+
+    pub fn text_generation(
+        &self,
+        app: &AppHandle,
+        model_id: String,
+    ) -> Result<Box<dyn TextGeneration>> {
+        let config = self
+            .load_config(app)
+            .with_context(|| format!("Failed to load {} configuration", self))?;
+
+        self.text_generation_with_config(config, model_id)
+    }
+}
+
+#[async_trait::async_trait]
+pub trait TextGeneration: Send + Sync {
+    fn get_provider(&self) -> Provider;
+
+    /// Number of tokens the model accepts in a single request, prompt and output combined.
+    fn context_window(&self) -> usize {
+        DEFAULT_CONTEXT_WINDOW
+    }
+
+    async fn get_models(&self) -> Result<Vec<Model>>;
+
+    async fn generate_text(&self, messages: Vec<Message>) -> Result<Vec<Message>>;
+
+    fn generate_text_stream(
+        &self,
+        messages: Vec<Message>,
+        cancellation_token: CancellationToken,
+    ) -> BoxStream<'_, Result<String>>;
+
+    /// Returns `false` when the provider rejects the credentials this client was built with.
+    async fn validate_api_key(&self) -> Result<bool> {
+        match self.get_models().await {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                if let Some(reqwest_error) = e.downcast_ref::<reqwest::Error>() {
+                    if reqwest_error.status() == Some(StatusCode::UNAUTHORIZED)
+                        || reqwest_error.status() == Some(StatusCode::FORBIDDEN)
+                        || reqwest_error.status() == Some(StatusCode::BAD_REQUEST)
+                    {
+                        return Ok(false);
+                    }
+                }
+
+                Err(e)
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    pub id: String,
+    pub name: String,
+    pub provider: Provider,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::System => write!(f, "system"),
+            Role::User => write!(f, "user"),
+            Role::Assistant => write!(f, "assistant"),
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "system" => Ok(Role::System),
+            "user" => Ok(Role::User),
+            "assistant" => Ok(Role::Assistant),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Type<Sqlite> for Role {
+    fn type_info() -> sqlx::sqlite::SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for Role {
+    fn decode(
+        value: <Sqlite as sqlx::Database>::ValueRef<'r>,
+    ) -> std::result::Result<Self, sqlx::error::BoxDynError> {
+        let s = <String as Decode<Sqlite>>::decode(value)?;
+        Role::from_str(&s).map_err(|_| "Failed to decode Role from database".into())
+    }
+}
+
+impl<'r> Encode<'r, Sqlite> for Role {
+    fn encode_by_ref(
+        &self,
+        args: &mut Vec<SqliteArgumentValue<'r>>,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        let s = self.to_string();
+        <String as Encode<Sqlite>>::encode_by_ref(&s, args)
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Message {
+    pub role: Role,
+    pub text: String,
+}
+
+pub async fn get_text_generation(app: &AppHandle) -> Result<Box<dyn TextGeneration>> {
+    let store = get_settings_store(app).context("Failed to get settings store")?;
+    // Older settings files only stored the model, which always belonged to Gemini
+    let provider: Provider = store
+        .get("model.textGeneration.provider")
+        .map(serde_json::from_value)
+        .transpose()
+        .context("Failed to parse text generation provider from settings")?
+        .unwrap_or(Provider::Gemini);
+    let text_generation_model: String = serde_json::from_value(
+        store
+            .get("model.textGeneration.model")
+            .context("Failed to get textGeneration model from settings store")?,
+    )
+    .context("Failed to parse text generation model from settings")?;
+
+    provider
+        .text_generation(app, text_generation_model)
+        .with_context(|| format!("Failed to initialize {} text generation client", provider))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+
+    #[tokio::test]
+    async fn test_gemini_generate_text() {
+        let gemini = gemini::Gemini::new_with_api_key(
+            Provider::Gemini
+                .get_api_key()
+                .expect("Failed to retrieve Gemini API key"),
+            "gemini-2.5-flash".to_string(),
+        );
+        let messages = vec![
+            Message {
+                role: Role::System,
+                text: "You are a helpful assistant.".to_string(),
+            },
+            Message {
+                role: Role::User,
+                text: "Hello, how are you?".to_string(),
+            },
+        ];
+        let response = gemini
+            .generate_text(messages)
+            .await
+            .expect("Failed to generate text");
+
+        for message in response {
+            println!("Role: {:?}, Text: {}", message.role, message.text);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gemini_generate_text_stream() {
+        let gemini = gemini::Gemini::new_with_api_key(
+            Provider::Gemini
+                .get_api_key()
+                .expect("Failed to retrieve Gemini API key"),
+            "gemini-2.5-flash".to_string(),
+        );
+        let messages = vec![
+            Message {
+                role: Role::System,
+                text: "You are a helpful assistant.".to_string(),
+            },
+            Message {
+                role: Role::User,
+                text: "Write a poem abou".to_string(),
+            },
+        ];
+
+        let cancellation_token = CancellationToken::new();
+        let mut stream = gemini.generate_text_stream(messages, cancellation_token.clone());
+
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(text) => print!("{}", text),
+                Err(e) => eprintln!("Stream error: {}", e),
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use anyhow::{Context, Result};
+use futures_util::{stream::BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::select;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use super::{
+    Message as TextGenerationMessage, Model as TextGenerationModel, Provider, Role, TextGeneration,
+};
+use crate::api::HTTP;
+
+pub const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+// Ollama silently truncates prompts beyond `num_ctx`, so it is always requested explicitly
+const CONTEXT_SIZE: usize = 8192;
+
+/// Client for a local Ollama server. Everything stays on the machine, so no API key is needed.
+pub struct Ollama {
+    base_url: String,
+    model_id: String,
+}
+
+#[derive(Deserialize)]
+struct Model {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct GetModelsResponse {
+    models: Vec<Model>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Message {
+    role: String,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct ChatOptions {
+    num_ctx: usize,
+}
+
+#[derive(Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<Message>,
+    stream: bool,
+    options: ChatOptions,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    message: Option<Message>,
+    #[serde(default)]
+    done: bool,
+    error: Option<String>,
+}
+
+impl Ollama {
+    pub fn new(base_url: String, model_id: String) -> Self {
+        Ollama {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model_id,
+        }
+    }
+
+    fn chat_request(&self, messages: Vec<TextGenerationMessage>, stream: bool) -> ChatRequest {
+        ChatRequest {
+            model: self.model_id.clone(),
+            messages: messages
+                .into_iter()
+                .map(|m| Message {
+                    role: m.role.to_string(),
+                    content: m.text,
+                })
+                .collect(),
+            stream,
+            options: ChatOptions {
+                num_ctx: CONTEXT_SIZE,
+            },
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TextGeneration for Ollama {
+    fn get_provider(&self) -> Provider {
+        Provider::Ollama
+    }
+
+    fn context_window(&self) -> usize {
+        CONTEXT_SIZE
+    }
+
+    async fn get_models(&self) -> Result<Vec<TextGenerationModel>> {
+        let response = HTTP
+            .get(format!("{}/api/tags", self.base_url))
+            .send()
+            .await
+            .context("Failed to reach Ollama server")?
+            .error_for_status()
+            .context("Failed to fetch installed Ollama models")?
+            .json::<GetModelsResponse>()
+            .await
+            .context("Failed to parse models response in JSON format")?;
+
+        let models = response
+            .models
+            .into_iter()
+            .map(|model| TextGenerationModel {
+                id: model.name.clone(),
+                name: model.name,
+                provider: Provider::Ollama,
+            })
+            .collect();
+
+        Ok(models)
+    }
+
+    async fn generate_text(
+        &self,
+        mut messages: Vec<TextGenerationMessage>,
+    ) -> Result<Vec<TextGenerationMessage>> {
+        let request = self.chat_request(messages.clone(), false);
+        let response = HTTP
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send text generation request")?
+            .error_for_status()
+            .context("Text generation request failed")?
+            .json::<ChatResponse>()
+            .await
+            .context("Failed to read text generation response")?;
+
+        if let Some(error) = response.error {
+            return Err(anyhow::anyhow!("Ollama returned an error: {}", error));
+        }
+        if let Some(message) = response.message {
+            messages.push(TextGenerationMessage {
+                role: Role::Assistant,
+                text: message.content,
+            });
+        }
+
+        Ok(messages)
+    }
+
+    fn generate_text_stream(
+        &self,
+        messages: Vec<TextGenerationMessage>,
+        cancellation_token: CancellationToken,
+    ) -> BoxStream<'_, Result<String>> {
+        let request = HTTP
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&self.chat_request(messages, true));
+
+        Box::pin(async_stream::try_stream! {
+            let response = request
+                .send()
+                .await
+                .context("Failed to send text generation request")?
+                .error_for_status()
+                .context("Text generation request failed")?;
+            let mut bytes = response.bytes_stream();
+            // Ollama streams newline-delimited JSON, and a line can be split across chunks
+            let mut buffer = Vec::new();
+
+            loop {
+                let next_step = select! {
+                    _ = cancellation_token.cancelled() => {
+                        None
+                    }
+                    chunk = bytes.next() => {
+                        Some(chunk)
+                    }
+                };
+
+                let chunk = match next_step {
+                    None => {
+                        debug!("Stream cancelled by token");
+                        break;
+                    }
+                    Some(None) => {
+                        debug!("Stream ended naturally");
+                        break;
+                    }
+                    Some(Some(chunk)) => chunk.context("Stream connection error")?,
+                };
+                buffer.extend_from_slice(&chunk);
+
+                let mut done = false;
+                while let Some(newline) = buffer.iter().position(|b| *b == b'\n') {
+                    let line = buffer.drain(..=newline).collect::<Vec<u8>>();
+                    if line.iter().all(u8::is_ascii_whitespace) {
+                        continue;
+                    }
+
+                    let response = serde_json::from_slice::<ChatResponse>(&line)
+                        .context("Failed to parse chunk")?;
+                    if let Some(error) = response.error {
+                        Err(anyhow::anyhow!("Ollama returned an error: {}", error))?;
+                    }
+                    if let Some(message) = response.message {
+                        if !message.content.is_empty() {
+                            yield message.content;
+                        }
+                    }
+                    if response.done {
+                        done = true;
+                        break;
+                    }
+                }
+
+                if done {
+                    break;
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,257 @@
+use anyhow::{Context, Result};
+use futures_util::{stream::BoxStream, StreamExt};
+use reqwest::RequestBuilder;
+use reqwest_eventsource::{Event, EventSource};
+use serde::{Deserialize, Serialize};
+use tokio::select;
+use tokio_util::sync::CancellationToken;
+use tracing::debug;
+
+use super::{
+    Message as TextGenerationMessage, Model as TextGenerationModel, Provider, Role, TextGeneration,
+};
+use crate::api::HTTP;
+
+#[derive(Serialize)]
+pub struct ChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<Message>,
+    pub stream: bool,
+}
+
+#[derive(Serialize)]
+pub struct Message {
+    pub role: String,
+    pub content: String,
+}
+
+impl From<TextGenerationMessage> for Message {
+    fn from(message: TextGenerationMessage) -> Self {
+        Message {
+            role: message.role.to_string(),
+            content: message.text,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ChatCompletionResponse {
+    pub choices: Vec<ChatCompletionResponseChoice>,
+}
+
+#[derive(Deserialize)]
+pub struct ChatCompletionResponseChoice {
+    pub message: ChatCompletionResponseMessage,
+}
+
+#[derive(Deserialize)]
+pub struct ChatCompletionResponseMessage {
+    pub content: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ChatCompletionChunk {
+    pub choices: Vec<ChatCompletionChoice>,
+}
+
+#[derive(Deserialize)]
+pub struct ChatCompletionChoice {
+    pub delta: ChatCompletionDelta,
+}
+
+#[derive(Deserialize)]
+pub struct ChatCompletionDelta {
+    pub content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ModelObject {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct GetModelsResponse {
+    data: Vec<ModelObject>,
+}
+
+/// Streams the content deltas of a chat completion request sent with `stream: true`.
+pub fn stream_chat_completion(
+    request: RequestBuilder,
+    cancellation_token: CancellationToken,
+) -> BoxStream<'static, Result<String>> {
+    Box::pin(async_stream::try_stream! {
+        let mut es = EventSource::new(request)
+            .context("Failed to create event source")?;
+
+        loop {
+            let next_step = select! {
+                _ = cancellation_token.cancelled() => {
+                    None
+                }
+                event = es.next() => {
+                    Some(event)
+                }
+            };
+
+            match next_step {
+                None => {
+                    debug!("Stream cancelled by token");
+                    es.close();
+                    break;
+                }
+
+                Some(None) => {
+                    debug!("Stream ended naturally");
+                    break;
+                }
+
+                Some(Some(Err(e))) => {
+                    es.close();
+                    Err(anyhow::anyhow!("Stream connection error: {}", e))?;
+                }
+
+                Some(Some(Ok(event))) => {
+                    match event {
+                        Event::Open => {
+                            debug!("Connection opened");
+                            continue;
+                        }
+                        Event::Message(message) => {
+                            if message.data == "[DONE]" {
+                                break;
+                            }
+
+                            debug!("Received message data: {}", &message.data);
+
+                            let chunk = match serde_json::from_str::<ChatCompletionChunk>(&message.data) {
+                                Ok(c) => c,
+                                Err(e) => {
+                                    Err(anyhow::anyhow!("Failed to parse chunk: {}", e))?
+                                }
+                            };
+
+                            for choice in chunk.choices {
+                                if let Some(content) = choice.delta.content {
+                                    yield content;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Any server that speaks the OpenAI chat completions API, e.g. vLLM, LM Studio or llama.cpp server.
+pub struct OpenAiCompatible {
+    base_url: String,
+    api_key: Option<String>,
+    model_id: String,
+}
+
+impl OpenAiCompatible {
+    pub fn new(base_url: String, api_key: Option<String>, model_id: String) -> Self {
+        OpenAiCompatible {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            model_id,
+        }
+    }
+
+    fn with_auth(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+
+    fn chat_completion_request(&self, messages: Vec<Message>, stream: bool) -> RequestBuilder {
+        self.with_auth(HTTP.post(format!("{}/chat/completions", self.base_url)))
+            .json(&ChatCompletionRequest {
+                model: self.model_id.clone(),
+                messages,
+                stream,
+            })
+    }
+}
+
+#[async_trait::async_trait]
+impl TextGeneration for OpenAiCompatible {
+    fn get_provider(&self) -> Provider {
+        Provider::OpenAiCompatible
+    }
+
+    async fn get_models(&self) -> Result<Vec<TextGenerationModel>> {
+        let response = self
+            .with_auth(HTTP.get(format!("{}/models", self.base_url)))
+            .send()
+            .await
+            .context("Failed to fetch models")?
+            .error_for_status()
+            .context("Failed to fetch models from OpenAI-compatible server")?
+            .json::<GetModelsResponse>()
+            .await
+            .context("Failed to parse models response in JSON format")?;
+
+        let models = response
+            .data
+            .into_iter()
+            .map(|model| TextGenerationModel {
+                name: model.id.clone(),
+                id: model.id,
+                provider: Provider::OpenAiCompatible,
+            })
+            .collect();
+
+        Ok(models)
+    }
+
+    async fn generate_text(
+        &self,
+        mut messages: Vec<TextGenerationMessage>,
+    ) -> Result<Vec<TextGenerationMessage>> {
+        let request_messages = messages
+            .iter()
+            .map(|m| Message {
+                role: m.role.to_string(),
+                content: m.text.clone(),
+            })
+            .collect();
+        let response = self
+            .chat_completion_request(request_messages, false)
+            .send()
+            .await
+            .context("Failed to send text generation request")?
+            .error_for_status()
+            .context("Text generation request failed")?
+            .json::<ChatCompletionResponse>()
+            .await
+            .context("Failed to read text generation response")?;
+
+        if let Some(content) = response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+        {
+            messages.push(TextGenerationMessage {
+                role: Role::Assistant,
+                text: content,
+            });
+        }
+
+        Ok(messages)
+    }
+
+    fn generate_text_stream(
+        &self,
+        messages: Vec<TextGenerationMessage>,
+        cancellation_token: CancellationToken,
+    ) -> BoxStream<'_, Result<String>> {
+        let request =
+            self.chat_completion_request(messages.into_iter().map(Message::from).collect(), true);
+
+        stream_chat_completion(request, cancellation_token)
+    }
+}
@@ -0,0 +1,256 @@
+use anyhow::Context;
+use sqlx::SqlitePool;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::ErrorCode,
+    features::{
+        speaker::entities::{Speaker, VoiceProfile},
+        summarize::diarization::VoiceEmbedding,
+    },
+};
+
+async fn fetch_speaker(database: &SqlitePool, speaker_id: Uuid) -> Result<Speaker, ErrorCode> {
+    sqlx::query_as::<_, Speaker>("SELECT * FROM speakers WHERE id = ?")
+        .bind(speaker_id)
+        .fetch_optional(database)
+        .await
+        .context("Failed to fetch speaker from database")?
+        .ok_or_else(|| ErrorCode::NotFound(format!("Speaker with id {} not found", speaker_id)))
+}
+
+fn parse_embedding(embedding: &str) -> anyhow::Result<VoiceEmbedding> {
+    serde_json::from_str(embedding).context("Failed to parse speaker voice")
+}
+
+#[tauri::command]
+pub async fn get_speakers(
+    database: State<'_, SqlitePool>,
+    summary_id: Uuid,
+) -> Result<Vec<Speaker>, ErrorCode> {
+    let speakers = sqlx::query_as::<_, Speaker>(
+        "SELECT * FROM speakers WHERE summary_id = ? ORDER BY created_at ASC",
+    )
+    .bind(summary_id)
+    .fetch_all(database.inner())
+    .await
+    .context("Failed to fetch speakers from database")?;
+
+    Ok(speakers)
+}
+
+/// Renames a speaker of one summary, e.g. "Speaker 1" to "Aiko". The stored transcript follows,
+/// the summary itself changes once it is regenerated.
+#[tauri::command]
+pub async fn rename_speaker(
+    database: State<'_, SqlitePool>,
+    speaker_id: Uuid,
+    name: String,
+) -> Result<Speaker, ErrorCode> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(ErrorCode::invalid_input("Speaker name must not be empty"));
+    }
+    fetch_speaker(database.inner(), speaker_id).await?;
+
+    let mut tx = database
+        .begin()
+        .await
+        .context("Failed to begin database transaction")?;
+    sqlx::query("UPDATE speakers SET name = ? WHERE id = ?")
+        .bind(&name)
+        .bind(speaker_id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to rename speaker")?;
+    sqlx::query("UPDATE summary_transcripts SET speaker = ? WHERE speaker_id = ?")
+        .bind(&name)
+        .bind(speaker_id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to rename speaker in transcript")?;
+    tx.commit()
+        .await
+        .context("Failed to commit database transaction")?;
+
+    fetch_speaker(database.inner(), speaker_id).await
+}
+
+/// Folds `source_id` into `target_id` when diarization split one person into two speakers.
+#[tauri::command]
+pub async fn merge_speakers(
+    database: State<'_, SqlitePool>,
+    source_id: Uuid,
+    target_id: Uuid,
+) -> Result<Speaker, ErrorCode> {
+    let source = fetch_speaker(database.inner(), source_id).await?;
+    let target = fetch_speaker(database.inner(), target_id).await?;
+    if source.id == target.id || source.summary_id != target.summary_id {
+        return Err(ErrorCode::invalid_input(
+            "Only two different speakers of the same summary can be merged",
+        ));
+    }
+
+    let embedding = match (&target.embedding, &source.embedding) {
+        (Some(target), Some(source)) => {
+            let mut embedding = parse_embedding(target)?;
+            embedding.merge(&parse_embedding(source)?);
+            Some(serde_json::to_string(&embedding).context("Failed to serialize speaker voice")?)
+        }
+        (target, source) => target.clone().or_else(|| source.clone()),
+    };
+
+    let mut tx = database
+        .begin()
+        .await
+        .context("Failed to begin database transaction")?;
+    sqlx::query("UPDATE summary_transcripts SET speaker_id = ?, speaker = ? WHERE speaker_id = ?")
+        .bind(target.id)
+        .bind(&target.name)
+        .bind(source.id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to move transcript to merged speaker")?;
+    sqlx::query("UPDATE speakers SET embedding = ?, profile_id = ? WHERE id = ?")
+        .bind(&embedding)
+        .bind(target.profile_id.or(source.profile_id))
+        .bind(target.id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to update merged speaker")?;
+    sqlx::query("DELETE FROM speakers WHERE id = ?")
+        .bind(source.id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to delete merged speaker")?;
+    tx.commit()
+        .await
+        .context("Failed to commit database transaction")?;
+
+    fetch_speaker(database.inner(), target.id).await
+}
+
+#[tauri::command]
+pub async fn get_voice_profiles(
+    database: State<'_, SqlitePool>,
+) -> Result<Vec<VoiceProfile>, ErrorCode> {
+    let profiles =
+        sqlx::query_as::<_, VoiceProfile>("SELECT * FROM voice_profiles ORDER BY name ASC")
+            .fetch_all(database.inner())
+            .await
+            .context("Failed to fetch voice profiles from database")?;
+
+    Ok(profiles)
+}
+
+/// Remembers the voice of a speaker so future recordings label them by name. Without
+/// `profile_id` a new profile named after the speaker is created, otherwise the voice is added
+/// to that profile, which makes recognition more reliable.
+#[tauri::command]
+pub async fn enroll_speaker(
+    database: State<'_, SqlitePool>,
+    speaker_id: Uuid,
+    profile_id: Option<Uuid>,
+) -> Result<VoiceProfile, ErrorCode> {
+    let speaker = fetch_speaker(database.inner(), speaker_id).await?;
+    let Some(embedding) = speaker.embedding.as_deref() else {
+        return Err(ErrorCode::invalid_input(
+            "This speaker has no recorded voice to enroll",
+        ));
+    };
+    let mut embedding = parse_embedding(embedding)?;
+
+    let mut tx = database
+        .begin()
+        .await
+        .context("Failed to begin database transaction")?;
+    let profile_id = match profile_id {
+        Some(profile_id) => {
+            let profile =
+                sqlx::query_as::<_, VoiceProfile>("SELECT * FROM voice_profiles WHERE id = ?")
+                    .bind(profile_id)
+                    .fetch_optional(&mut *tx)
+                    .await
+                    .context("Failed to fetch voice profile from database")?
+                    .ok_or_else(|| {
+                        ErrorCode::NotFound(format!(
+                            "Voice profile with id {} not found",
+                            profile_id
+                        ))
+                    })?;
+            embedding.merge(&parse_embedding(&profile.embedding)?);
+
+            sqlx::query("UPDATE voice_profiles SET embedding = ? WHERE id = ?")
+                .bind(serde_json::to_string(&embedding).context("Failed to serialize voice")?)
+                .bind(profile.id)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to update voice profile")?;
+            sqlx::query("UPDATE speakers SET name = ? WHERE id = ?")
+                .bind(&profile.name)
+                .bind(speaker.id)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to rename enrolled speaker")?;
+            sqlx::query("UPDATE summary_transcripts SET speaker = ? WHERE speaker_id = ?")
+                .bind(&profile.name)
+                .bind(speaker.id)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to rename speaker in transcript")?;
+
+            profile.id
+        }
+        None => {
+            let profile_id = Uuid::new_v4();
+            sqlx::query("INSERT INTO voice_profiles (id, name, embedding) VALUES (?, ?, ?)")
+                .bind(profile_id)
+                .bind(&speaker.name)
+                .bind(serde_json::to_string(&embedding).context("Failed to serialize voice")?)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to insert voice profile into database")?;
+
+            profile_id
+        }
+    };
+    sqlx::query("UPDATE speakers SET profile_id = ? WHERE id = ?")
+        .bind(profile_id)
+        .bind(speaker.id)
+        .execute(&mut *tx)
+        .await
+        .context("Failed to link speaker to voice profile")?;
+    tx.commit()
+        .await
+        .context("Failed to commit database transaction")?;
+
+    let profile = sqlx::query_as::<_, VoiceProfile>("SELECT * FROM voice_profiles WHERE id = ?")
+        .bind(profile_id)
+        .fetch_one(database.inner())
+        .await
+        .context("Failed to fetch voice profile from database")?;
+
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn delete_voice_profile(
+    database: State<'_, SqlitePool>,
+    profile_id: Uuid,
+) -> Result<(), ErrorCode> {
+    let result = sqlx::query("DELETE FROM voice_profiles WHERE id = ?")
+        .bind(profile_id)
+        .execute(database.inner())
+        .await
+        .context("Failed to delete voice profile")?;
+
+    if result.rows_affected() == 0 {
+        return Err(ErrorCode::NotFound(format!(
+            "Voice profile with id {} not found",
+            profile_id
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+use crate::features::summarize::diarization::VoiceEmbedding;
+
+#[derive(Clone, FromRow, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Speaker {
+    pub id: Uuid,
+    pub summary_id: Uuid,
+    pub name: String,
+    /// Enrolled voice this speaker was recognized as or enrolled into
+    pub profile_id: Option<Uuid>,
+    /// [`VoiceEmbedding`] serialized as JSON, missing for speakers diarized before voices were kept
+    #[serde(skip)]
+    pub embedding: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Clone, FromRow, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceProfile {
+    pub id: Uuid,
+    pub name: String,
+    /// [`VoiceEmbedding`] serialized as JSON
+    #[serde(skip)]
+    pub embedding: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Speaker of a recording that has not been saved as a summary yet.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DiarizedSpeaker {
+    /// Label used by the transcript segments, the profile name for recognized voices
+    pub name: String,
+    pub profile_id: Option<Uuid>,
+    /// Missing for speakers named after their own audio track instead of diarized
+    pub embedding: Option<VoiceEmbedding>,
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use sqlx::{SqliteConnection, SqlitePool};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::features::{
+    model::speech_to_text::Segment,
+    speaker::entities::{DiarizedSpeaker, VoiceProfile},
+    summarize::diarization::{speaker_label, VoiceEmbedding},
+};
+
+/// Names the speakers of a diarized recording after the enrolled voices they match and
+/// relabels the segments accordingly. `voices[0]` is the voice of "Speaker 1".
+pub async fn recognize_speakers(
+    database: &SqlitePool,
+    segments: &mut [Segment],
+    voices: Vec<VoiceEmbedding>,
+) -> Result<Vec<DiarizedSpeaker>> {
+    let profiles = sqlx::query_as::<_, VoiceProfile>("SELECT * FROM voice_profiles")
+        .fetch_all(database)
+        .await
+        .context("Failed to fetch voice profiles from database")?
+        .into_iter()
+        .filter_map(
+            |profile| match serde_json::from_str::<VoiceEmbedding>(&profile.embedding) {
+                Ok(embedding) => Some((profile, embedding)),
+                Err(e) => {
+                    warn!(id=%profile.id, error=%e, "Ignoring voice profile with invalid embedding");
+                    None
+                }
+            },
+        )
+        .collect::<Vec<_>>();
+    let profile_voices = profiles
+        .iter()
+        .map(|(_, embedding)| embedding.clone())
+        .collect::<Vec<_>>();
+
+    let mut speakers: Vec<DiarizedSpeaker> = Vec::new();
+    let mut names: HashMap<String, String> = HashMap::new();
+    for (index, voice) in voices.into_iter().enumerate() {
+        let profile = voice
+            .closest(&profile_voices)
+            .map(|closest| &profiles[closest].0);
+
+        // Two clusters matching the same person are one speaker who sounded different at times
+        let existing = profile.and_then(|profile| {
+            speakers
+                .iter_mut()
+                .find(|speaker| speaker.profile_id == Some(profile.id))
+        });
+        let name = match existing {
+            Some(speaker) => {
+                if let Some(embedding) = speaker.embedding.as_mut() {
+                    embedding.merge(&voice);
+                }
+                speaker.name.clone()
+            }
+            None => {
+                let name = match profile {
+                    Some(profile) => {
+                        info!(profile=%profile.id, "Recognized enrolled speaker");
+                        profile.name.clone()
+                    }
+                    None => speaker_label(speakers.len() + 1),
+                };
+                speakers.push(DiarizedSpeaker {
+                    name: name.clone(),
+                    profile_id: profile.map(|profile| profile.id),
+                    embedding: Some(voice),
+                });
+                name
+            }
+        };
+        names.insert(speaker_label(index + 1), name);
+    }
+
+    for segment in segments.iter_mut() {
+        if let Some(name) = segment.speaker.as_ref().and_then(|label| names.get(label)) {
+            segment.speaker = Some(name.clone());
+        }
+    }
+
+    Ok(speakers)
+}
+
+/// Stores the speakers of a new summary and returns their ids by name.
+pub async fn save_speakers(
+    connection: &mut SqliteConnection,
+    summary_id: Uuid,
+    speakers: &[DiarizedSpeaker],
+) -> Result<HashMap<String, Uuid>> {
+    let mut ids = HashMap::with_capacity(speakers.len());
+    for speaker in speakers {
+        let speaker_id = Uuid::new_v4();
+        let embedding = speaker
+            .embedding
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .context("Failed to serialize speaker voice")?;
+        sqlx::query(
+            "INSERT INTO speakers (id, summary_id, name, profile_id, embedding) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(speaker_id)
+        .bind(summary_id)
+        .bind(&speaker.name)
+        .bind(speaker.profile_id)
+        .bind(&embedding)
+        .execute(&mut *connection)
+        .await
+        .context("Failed to insert speaker into database")?;
+
+        ids.insert(speaker.name.clone(), speaker_id);
+    }
+
+    Ok(ids)
+}
@@ -1,10 +1,22 @@
-use anyhow::{Context, Error, Result};
+use anyhow::{ensure, Context, Error, Result};
 use infer::Infer;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use strum_macros::Display;
 use tokio::fs::File;
-use tokio::io::AsyncReadExt;
-use tokio::process::Command;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::{Child, ChildStdout, Command};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::{
+    features::summarize::{
+        decoder::{decode_native, NATIVE_MIMETYPES},
+        media::{probe_media, MediaInfo},
+    },
+    utils::sqlite::impl_sqlite_json,
+};
 
 const ALLOWED_FILE_MIMETYPES: [&'static str; 10] = [
     "audio/mpeg",
@@ -19,7 +31,174 @@ const ALLOWED_FILE_MIMETYPES: [&'static str; 10] = [
     "video/quicktime",
 ];
 
-async fn validate_file_type(path: &PathBuf) -> Result<(), Error> {
+/// Sample rate of the mono audio returned by [`load_f32le_audio`], as expected by Whisper.
+pub const SAMPLE_RATE: usize = 16000;
+
+/// Part of a recording to decode, for files with several audio streams like OBS or Zoom
+/// exports. The default decodes the file's main audio stream with all channels mixed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AudioSelection {
+    /// Audio streams by their index in the file, as reported by probing. Several streams are
+    /// mixed together unless `separate_tracks` is set.
+    pub streams: Vec<u32>,
+    /// Channels to keep from every selected stream, starting at 0. Empty keeps all channels.
+    pub channels: Vec<u16>,
+    /// Transcribes every selected stream on its own and labels its segments with the stream's
+    /// name, which separates speakers recorded on their own track.
+    pub separate_tracks: bool,
+}
+
+impl_sqlite_json!(AudioSelection);
+
+impl AudioSelection {
+    /// Checks that the selected streams and channels exist in the probed file.
+    pub fn validate(&self, media: &MediaInfo) -> Result<()> {
+        let streams = if self.streams.is_empty() {
+            media.audio_streams.iter().take(1).collect::<Vec<_>>()
+        } else {
+            self.streams
+                .iter()
+                .map(|index| {
+                    media
+                        .audio_streams
+                        .iter()
+                        .find(|stream| stream.index == *index)
+                        .with_context(|| format!("File has no audio stream {}", index))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        for stream in streams {
+            let Some(count) = stream.channels else {
+                continue;
+            };
+            for channel in &self.channels {
+                ensure!(
+                    *channel < count,
+                    "Audio stream {} has no channel {}",
+                    stream.index,
+                    channel
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Splits the selection into the tracks to transcribe, each with its label. Without
+    /// `separate_tracks` there is a single unlabelled track.
+    pub fn tracks(&self, media: &MediaInfo) -> Vec<(Option<String>, AudioSelection)> {
+        if !self.separate_tracks || self.streams.len() < 2 {
+            return vec![(None, self.clone())];
+        }
+
+        let mut tracks: Vec<(Option<String>, AudioSelection)> = Vec::new();
+        for (n, index) in self.streams.iter().enumerate() {
+            let title = media
+                .audio_streams
+                .iter()
+                .find(|stream| stream.index == *index)
+                .and_then(|stream| stream.title.clone())
+                .filter(|title| !title.trim().is_empty());
+            // Labels name the speakers, so two tracks must not share one
+            let label = match title {
+                Some(title)
+                    if !tracks
+                        .iter()
+                        .any(|(label, _)| label.as_ref() == Some(&title)) =>
+                {
+                    title
+                }
+                _ => format!("Track {}", n + 1),
+            };
+
+            tracks.push((
+                Some(label),
+                AudioSelection {
+                    streams: vec![*index],
+                    channels: self.channels.clone(),
+                    separate_tracks: false,
+                },
+            ));
+        }
+
+        tracks
+    }
+
+    /// ffmpeg arguments that pick the streams and channels, placed after the input.
+    fn ffmpeg_args(&self) -> Vec<String> {
+        let pan = (!self.channels.is_empty()).then(|| {
+            let weight = 1.0 / self.channels.len() as f64;
+            let sum = self
+                .channels
+                .iter()
+                .map(|channel| format!("{}*c{}", weight, channel))
+                .collect::<Vec<_>>()
+                .join("+");
+            format!("pan=mono|c0={}", sum)
+        });
+
+        match self.streams.as_slice() {
+            [] => pan
+                .map(|pan| vec!["-af".to_string(), pan])
+                .unwrap_or_default(),
+            [index] => {
+                let mut args = vec!["-map".to_string(), format!("0:{}", index)];
+                if let Some(pan) = pan {
+                    args.extend(["-af".to_string(), pan]);
+                }
+                args
+            }
+            streams => {
+                let pan = pan.unwrap_or_else(|| "anull".to_string());
+                let mut graph = streams
+                    .iter()
+                    .enumerate()
+                    .map(|(n, index)| format!("[0:{}]{}[a{}];", index, pan, n))
+                    .collect::<String>();
+                graph.push_str(
+                    &(0..streams.len())
+                        .map(|n| format!("[a{}]", n))
+                        .collect::<String>(),
+                );
+                graph.push_str(&format!("amix=inputs={}[mix]", streams.len()));
+
+                vec![
+                    "-filter_complex".to_string(),
+                    graph,
+                    "-map".to_string(),
+                    "[mix]".to_string(),
+                ]
+            }
+        }
+    }
+}
+
+/// Part of a file to decode, in seconds from its start.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DecodeRange {
+    pub start: f64,
+    /// Decodes to the end of the file when missing
+    pub end: Option<f64>,
+}
+
+impl DecodeRange {
+    /// Input options that make ffmpeg seek instead of decoding the skipped audio.
+    fn ffmpeg_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.start > 0.0 {
+            args.extend(["-ss".to_string(), self.start.to_string()]);
+        }
+        if let Some(end) = self.end {
+            args.extend(["-t".to_string(), (end - self.start).max(0.0).to_string()]);
+        }
+        args
+    }
+}
+
+/// Returns the mime type of a supported media file, based on its content.
+pub async fn detect_mime_type(path: &PathBuf) -> Result<&'static str, Error> {
     let mut file = File::open(path).await.context("Failed to open file.")?;
     let mut buffer = [0u8; 8192];
     let n = file.read(&mut buffer).await?;
@@ -35,15 +214,26 @@ async fn validate_file_type(path: &PathBuf) -> Result<(), Error> {
         )));
     }
 
+    Ok(kind.mime_type())
+}
+
+/// Checks that a file is a supported media file with at least one audio stream.
+pub async fn validate_file_type(path: &PathBuf) -> Result<(), Error> {
+    let media = probe_media(path).await?;
+    if media.audio_streams.is_empty() {
+        return Err(Error::msg("File has no audio stream"));
+    }
+
     Ok(())
 }
 
-fn get_ffmpeg_path() -> PathBuf {
+/// Path of a bundled ffmpeg tool, like `ffmpeg` or `ffprobe`.
+fn get_binary_path(name: &str) -> PathBuf {
     if cfg!(debug_assertions) {
-        // Development mode - use ffmpeg from PATH
-        PathBuf::from("ffmpeg")
+        // Development mode - use the tool from PATH
+        PathBuf::from(name)
     } else {
-        // Release mode - use bundled ffmpeg in executable directory
+        // Release mode - use the tool bundled in executable directory
         let exe_dir = std::env::current_exe()
             .expect("Failed to get executable path")
             .parent()
@@ -51,24 +241,162 @@ fn get_ffmpeg_path() -> PathBuf {
             .to_path_buf();
 
         #[cfg(target_os = "windows")]
-        let ffmpeg_name = "ffmpeg.exe";
+        let name = format!("{}.exe", name);
+
+        exe_dir.join("bin").join(name)
+    }
+}
+
+fn get_ffmpeg_path() -> PathBuf {
+    get_binary_path("ffmpeg")
+}
+
+pub(crate) fn get_ffprobe_path() -> PathBuf {
+    get_binary_path("ffprobe")
+}
+
+/// Samples in every chunk yielded by [`SampleReader`], except the last one: 30 seconds of audio.
+pub const CHUNK_SAMPLES: usize = 30 * SAMPLE_RATE;
+
+/// Reads raw f32le samples in chunks of [`CHUNK_SAMPLES`], so the whole recording never has to
+/// be held in memory at once.
+pub struct SampleReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> SampleReader<R> {
+    pub fn new(reader: R) -> Self {
+        SampleReader {
+            reader,
+            buffer: vec![0; CHUNK_SAMPLES * 4],
+        }
+    }
 
-        #[cfg(not(target_os = "windows"))]
-        let ffmpeg_name = "ffmpeg";
+    /// Returns the next chunk, or `None` once the stream is exhausted.
+    pub async fn next_chunk(&mut self) -> Result<Option<Vec<f32>>> {
+        let mut filled = 0;
+        while filled < self.buffer.len() {
+            let n = self
+                .reader
+                .read(&mut self.buffer[filled..])
+                .await
+                .context("Failed to read audio samples")?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+
+        if filled == 0 {
+            return Ok(None);
+        }
+        if filled % 4 != 0 {
+            return Err(Error::msg("Invalid f32le audio data length"));
+        }
 
-        exe_dir.join("bin").join(ffmpeg_name)
+        let samples = self.buffer[..filled]
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect::<Vec<f32>>();
+
+        Ok(Some(samples))
     }
 }
 
-pub async fn load_f32le_audio(path: &PathBuf) -> Result<Vec<f32>> {
-    validate_file_type(&path)
+/// Decoder behind an [`AudioStream`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum AudioDecoder {
+    #[strum(serialize = "ffmpeg")]
+    Ffmpeg,
+    /// symphonia, used for common audio formats when ffmpeg is not available
+    #[strum(serialize = "built-in decoder")]
+    Native,
+}
+
+enum AudioSource {
+    Ffmpeg {
+        child: Child,
+        samples: SampleReader<ChildStdout>,
+        /// Collects ffmpeg's log, which is only read when decoding fails
+        stderr: Option<JoinHandle<String>>,
+    },
+    Native {
+        chunks: mpsc::Receiver<Result<Vec<f32>>>,
+        /// Decoding thread, awaited once `chunks` closes so a panic is not mistaken for the end
+        decoder: Option<JoinHandle<()>>,
+    },
+}
+
+/// Audio read chunk by chunk while it is still being decoded.
+pub struct AudioStream {
+    source: AudioSource,
+}
+
+impl AudioStream {
+    pub fn decoder(&self) -> AudioDecoder {
+        match self.source {
+            AudioSource::Ffmpeg { .. } => AudioDecoder::Ffmpeg,
+            AudioSource::Native { .. } => AudioDecoder::Native,
+        }
+    }
+
+    /// Returns the next chunk of mono 16 kHz samples, or `None` once decoding finished.
+    pub async fn next_chunk(&mut self) -> Result<Option<Vec<f32>>> {
+        let (child, samples, stderr) = match &mut self.source {
+            AudioSource::Ffmpeg {
+                child,
+                samples,
+                stderr,
+            } => (child, samples, stderr),
+            AudioSource::Native { chunks, decoder } => {
+                if let Some(chunk) = chunks.recv().await {
+                    return chunk.map(Some);
+                }
+                if let Some(decoder) = decoder.take() {
+                    decoder.await.context("Built-in decoder panicked")?;
+                }
+                return Ok(None);
+            }
+        };
+
+        if let Some(chunk) = samples.next_chunk().await? {
+            return Ok(Some(chunk));
+        }
+
+        let status = child.wait().await.context("Failed to wait for ffmpeg")?;
+        if !status.success() {
+            let stderr = match stderr.take() {
+                Some(stderr) => stderr.await.unwrap_or_default(),
+                None => String::new(),
+            };
+            return Err(Error::msg(format!(
+                "ffmpeg command failed with error: {}",
+                stderr
+            )));
+        }
+
+        Ok(None)
+    }
+}
+
+/// Starts decoding a media file with ffmpeg, or with the built-in decoder when ffmpeg cannot be
+/// found and the format is one it supports. ffmpeg is killed when the stream is dropped early.
+pub async fn stream_f32le_audio(
+    path: &PathBuf,
+    selection: &AudioSelection,
+    range: DecodeRange,
+) -> Result<AudioStream> {
+    let mime_type = detect_mime_type(&path)
         .await
         .context("Failed to validate file type")?;
 
     let ffmpeg_path = get_ffmpeg_path();
-    let output = Command::new(&ffmpeg_path)
+    let spawned = Command::new(&ffmpeg_path)
+        .args(range.ffmpeg_args())
         .arg("-i")
         .arg(&path)
+        .args(selection.ffmpeg_args())
         .args(&[
             "-vn", // Disable video recording
             "-acodec",
@@ -81,29 +409,83 @@ pub async fn load_f32le_audio(path: &PathBuf) -> Result<Vec<f32>> {
             "f32le",  // Output format (raw PCM 32-bit little-endian)
             "pipe:1", // Output to Stdout
         ])
+        .stdin(Stdio::null())
         .stdout(Stdio::piped()) // Capture stdout
         .stderr(Stdio::piped()) // Capture stderr (untuk debug error)
-        .output()
-        .await
-        .context("Failed to execute ffmpeg command")?;
+        .kill_on_drop(true)
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if !NATIVE_MIMETYPES.contains(&mime_type) {
+                return Err(Error::msg(format!(
+                    "ffmpeg was not found at {}, it is required to decode {} files",
+                    ffmpeg_path.display(),
+                    mime_type
+                )));
+            }
+            if selection.streams.len() > 1 {
+                return Err(Error::msg(format!(
+                    "ffmpeg was not found at {}, it is required to mix audio streams",
+                    ffmpeg_path.display()
+                )));
+            }
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(Error::msg(format!(
-            "ffmpeg command failed with error: {}",
-            stderr
-        )));
-    }
+            return Ok(decode_native_stream(path, selection, range));
+        }
+        Err(e) => return Err(Error::new(e).context("Failed to execute ffmpeg command")),
+    };
 
-    let bytes = output.stdout;
-    if bytes.len() % 4 != 0 {
-        return Err(Error::msg("Invalid f32le audio data length"));
+    let stdout = child
+        .stdout
+        .take()
+        .context("Failed to capture ffmpeg output")?;
+    // ffmpeg blocks once the stderr pipe is full, so it is drained in the background
+    let stderr = child.stderr.take().map(|mut stderr| {
+        tokio::spawn(async move {
+            let mut log = String::new();
+            let _ = stderr.read_to_string(&mut log).await;
+            log
+        })
+    });
+
+    Ok(AudioStream {
+        source: AudioSource::Ffmpeg {
+            child,
+            samples: SampleReader::new(stdout),
+            stderr,
+        },
+    })
+}
+
+/// Starts decoding a media file with the built-in decoder on a blocking thread.
+fn decode_native_stream(
+    path: &Path,
+    selection: &AudioSelection,
+    range: DecodeRange,
+) -> AudioStream {
+    let (sender, receiver) = mpsc::channel(2);
+    let path = path.to_path_buf();
+    let selection = selection.clone();
+    let decoder =
+        tokio::task::spawn_blocking(move || decode_native(&path, &selection, range, sender));
+
+    AudioStream {
+        source: AudioSource::Native {
+            chunks: receiver,
+            decoder: Some(decoder),
+        },
     }
+}
 
-    let audio_data = bytes
-        .chunks_exact(4)
-        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-        .collect::<Vec<f32>>();
+/// Decodes a whole media file into memory. Prefer [`stream_f32le_audio`] for long recordings.
+pub async fn load_f32le_audio(path: &PathBuf) -> Result<Vec<f32>> {
+    let mut stream =
+        stream_f32le_audio(path, &AudioSelection::default(), DecodeRange::default()).await?;
+    let mut audio_data = Vec::new();
+    while let Some(chunk) = stream.next_chunk().await? {
+        audio_data.extend_from_slice(&chunk);
+    }
 
     Ok(audio_data)
 }
@@ -143,6 +525,66 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_selection_ffmpeg_args() {
+        assert!(AudioSelection::default().ffmpeg_args().is_empty());
+
+        let mic = AudioSelection {
+            streams: vec![2],
+            channels: vec![0],
+            separate_tracks: false,
+        };
+        assert_eq!(
+            mic.ffmpeg_args(),
+            ["-map", "0:2", "-af", "pan=mono|c0=1*c0"]
+        );
+
+        let mixed = AudioSelection {
+            streams: vec![1, 2],
+            channels: vec![0, 1],
+            separate_tracks: false,
+        };
+        assert_eq!(
+            mixed.ffmpeg_args(),
+            [
+                "-filter_complex",
+                "[0:1]pan=mono|c0=0.5*c0+0.5*c1[a0];[0:2]pan=mono|c0=0.5*c0+0.5*c1[a1];[a0][a1]amix=inputs=2[mix]",
+                "-map",
+                "[mix]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_range_ffmpeg_args() {
+        assert!(DecodeRange::default().ffmpeg_args().is_empty());
+
+        let range = DecodeRange {
+            start: 90.0,
+            end: Some(150.5),
+        };
+        assert_eq!(range.ffmpeg_args(), vec!["-ss", "90", "-t", "60.5"]);
+    }
+
+    #[tokio::test]
+    async fn test_sample_reader_chunks() {
+        let samples = (0..CHUNK_SAMPLES + 10)
+            .map(|i| i as f32)
+            .collect::<Vec<f32>>();
+        let bytes = samples
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect::<Vec<u8>>();
+        let mut reader = SampleReader::new(&bytes[..]);
+
+        let first = reader.next_chunk().await.unwrap().unwrap();
+        let second = reader.next_chunk().await.unwrap().unwrap();
+
+        assert_eq!(first.len(), CHUNK_SAMPLES);
+        assert_eq!(second, samples[CHUNK_SAMPLES..]);
+        assert!(reader.next_chunk().await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_load_f32le_audio() {
         let base_path = get_base_path();
@@ -167,4 +609,29 @@ mod tests {
             assert!(!f32le_data.is_empty());
         }
     }
+
+    #[tokio::test]
+    async fn test_decode_native_stream() {
+        let audio_path = get_base_path().join("audio");
+
+        let test_cases = vec![
+            audio_path.join("audio.mp3"),
+            audio_path.join("audio.wav"),
+            audio_path.join("audio.m4a"),
+            audio_path.join("audio.ogg"),
+            audio_path.join("audio.flac"),
+        ];
+
+        for path in test_cases {
+            let mut stream =
+                decode_native_stream(&path, &AudioSelection::default(), DecodeRange::default());
+            assert_eq!(stream.decoder(), AudioDecoder::Native);
+
+            let mut samples = 0;
+            while let Some(chunk) = stream.next_chunk().await.unwrap() {
+                samples += chunk.len();
+            }
+            assert!(samples > 0, "no audio decoded from {}", path.display());
+        }
+    }
 }
@@ -1,26 +1,27 @@
-use std::{path::PathBuf, vec};
+use std::{collections::HashMap, path::PathBuf};
 
 use crate::{
     error::ErrorCode,
     features::{
-        model::{
-            speech_to_text::{Segment, SpeechToTextModel, Whisper},
-            text_generation::{get_text_generation, Message, Role},
+        job::{
+            batch::BatchReport,
+            entities::{Job, JobOptions},
         },
+        model::speech_to_text::TranscriptionMode,
         summarize::{
-            audio::load_f32le_audio,
-            entities::Summary,
+            audio::AudioSelection,
+            entities::{Summary, SummaryTranscript, TranscriptSegment, TranscriptWord},
             language::{Language, LanguageInfo},
+            media::{self, MediaInfo},
         },
     },
-    utils::tauri::get_settings_store,
+    state::AppState,
 };
 use anyhow::{Context, Result};
-use serde::Serialize;
 use sqlx::SqlitePool;
 use strum::IntoEnumIterator;
-use tauri::{AppHandle, Emitter, Manager, State};
-use tracing::{error, info};
+use tauri::State;
+use tracing::info;
 use uuid::Uuid;
 
 #[tauri::command]
@@ -51,6 +52,78 @@ pub async fn get_summaries(database: State<'_, SqlitePool>) -> Result<Vec<Summar
     Ok(records)
 }
 
+/// Returns the transcript of a summary with word-level timing. Translated summaries can also
+/// return the transcript in the spoken language through `original`.
+#[tauri::command]
+pub async fn get_transcript(
+    database: State<'_, SqlitePool>,
+    summary_id: Uuid,
+    original: Option<bool>,
+) -> Result<Vec<TranscriptSegment>, ErrorCode> {
+    let original = original.unwrap_or(false);
+    let transcripts = sqlx::query_as::<_, SummaryTranscript>(
+        "SELECT * FROM summary_transcripts WHERE summary_id = ? AND is_original = ? ORDER BY start_time ASC",
+    )
+    .bind(&summary_id)
+    .bind(original)
+    .fetch_all(database.inner())
+    .await
+    .context("Failed to fetch summary transcripts from database")?;
+
+    let words = sqlx::query_as::<_, TranscriptWord>(
+        "SELECT w.* FROM transcript_words w JOIN summary_transcripts t ON t.id = w.transcript_id WHERE t.summary_id = ? AND t.is_original = ? ORDER BY w.position ASC",
+    )
+    .bind(&summary_id)
+    .bind(original)
+    .fetch_all(database.inner())
+    .await
+    .context("Failed to fetch transcript words from database")?;
+
+    let mut words_by_transcript: HashMap<Uuid, Vec<TranscriptWord>> = HashMap::new();
+    for word in words {
+        words_by_transcript
+            .entry(word.transcript_id)
+            .or_default()
+            .push(word);
+    }
+
+    let segments = transcripts
+        .into_iter()
+        .map(|transcript| TranscriptSegment {
+            words: words_by_transcript
+                .remove(&transcript.id)
+                .unwrap_or_default(),
+            transcript,
+        })
+        .collect();
+
+    Ok(segments)
+}
+
+/// Summarizes the stored transcript again, e.g. after speakers were renamed or merged.
+#[tauri::command]
+pub async fn regenerate_summary(
+    state: State<'_, AppState>,
+    summary_id: Uuid,
+) -> Result<Summary, ErrorCode> {
+    info!("Regenerating summary: {}", summary_id);
+
+    state
+        .job_manager
+        .regenerate(summary_id)
+        .await
+        .context("Failed to regenerate summary")?
+        .ok_or_else(|| ErrorCode::NotFound(format!("Summary with id {} not found", summary_id)))
+}
+
+#[tauri::command]
+pub async fn cancel_summary_regeneration(
+    state: State<'_, AppState>,
+    summary_id: Uuid,
+) -> Result<bool, ErrorCode> {
+    Ok(state.job_manager.cancel_regeneration(summary_id))
+}
+
 #[tauri::command]
 pub async fn delete_summary(
     database: State<'_, SqlitePool>,
@@ -75,230 +148,87 @@ pub async fn delete_summary(
     Ok(())
 }
 
-#[derive(Clone, Serialize)]
-struct SummarizationProgress {
-    pub message: String,
-    #[serde(rename = "currentStep")]
-    pub current_step: u8,
-    #[serde(rename = "totalSteps")]
-    pub total_steps: u8,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub summary: Option<Summary>,
+/// Reads duration, codecs, audio streams and tags of a file, so it can be checked before a
+/// long transcription is started.
+#[tauri::command]
+pub async fn probe_media(file_path: String) -> Result<MediaInfo, ErrorCode> {
+    let media = media::probe_media(&PathBuf::from(&file_path))
+        .await
+        .context("Failed to probe media file")?;
+
+    Ok(media)
 }
 
 #[tauri::command]
 pub async fn summarize(
-    app: AppHandle,
-    sqlite: State<'_, SqlitePool>,
+    state: State<'_, AppState>,
     language: Language,
     file_path: String,
-) -> Result<(), ErrorCode> {
+    transcription_mode: Option<TranscriptionMode>,
+    keep_original_transcript: Option<bool>,
+    glossary_ids: Option<Vec<Uuid>>,
+    audio_selection: Option<AudioSelection>,
+    appended_files: Option<Vec<String>>,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+) -> Result<Job, ErrorCode> {
     info!("Starting summarization for file: {:?}", file_path);
 
-    let database = sqlite.inner().clone();
-
-    tokio::spawn(async move {
-        if let Err(e) = async {
-            let mut tx = database
-                .begin()
-                .await
-                .context("Failed to begin database transaction")?;
-            let store = get_settings_store(&app)
-                .context("Failed to get settings store")?;
-            let stt_model: SpeechToTextModel = serde_json::from_value(
-                store
-                    .get("model.speechToText")
-                    .context("Failed to get speechToText model from settings store")?,
-            )
-            .context("Failed to parse speech-to-text model from settings")?;
-
-            let speech_to_text = Whisper::new(
-                app.path()
-                    .app_local_data_dir()
-                    .context("Could not determine app local data directory")?
-                    .join("models")
-                    .join(stt_model.filename()),
-            );
-            let text_generation = get_text_generation(store.as_ref())
-                .await
-                .context("Failed to initialize text generation model")?;
-
-            // Helper closure for emitting progress
-            let emit_progress = |message: &str, step: u8, summary: Option<Summary>| {
-                app.emit(
-                    "summarization_progress",
-                    SummarizationProgress {
-                        message: message.to_string(),
-                        current_step: step,
-                        total_steps: 4,
-                        summary,
-                    },
-                )
-                .context("Failed to emit summarization progress event")
-            };
-
-            // Step 1: Load audio
-            emit_progress("Loading audio...", 1, None)?;
-            let audio_data = load_f32le_audio(&PathBuf::from(&file_path))
-                .await
-                .context("Failed to load audio data")?;
-
-            // Step 2: Transcribe audio
-            emit_progress("Transcribing audio...", 2, None)?;
-            let segments = speech_to_text
-                .transcribe(audio_data, language)
-                .await
-                .context("Failed to transcript audio")?;
-
-            // Step 3: Generate summary and title
-            emit_progress("Generating summary...", 3, None)?;
-            let summarize_result = text_generation
-                .generate_text(
-                    vec![
-                        Message {
-                            role: Role::System,
-                            text: get_summary_prompt(language),
-                        },
-                        Message {
-                            role: Role::User,
-                            text: segments_to_text(segments.clone()),
-                        },
-                    ],
-                )
-                .await
-                .context("Failed to generate summary text")?
-                .into_iter()
-                .last()
-                .map(|m| m.text)
-                .unwrap_or_default();
-
-            let summary_title = text_generation
-                .generate_text(
-                    vec![
-                        Message {
-                            role: Role::System,
-                            text:
-                            "
-                            You will receive a summarized personal note written in Markdown.
-
-                            Assume this summary represents the main content of a personal voice note.
-                            Your task is to generate a **short, clear, and descriptive title** based only on the summary content.
-
-                            Title requirements:
-                            - One line only
-                            - No punctuation at the end
-                            - No quotation marks
-                            - No emojis
-                            - Do not add information not present in the summary
-                            - Keep it concise and neutral
-
-                            Write the title in the same language as the summary.
-                            ".to_string(),
-                        },
-                        Message {
-                            role: Role::User,
-                            text: summarize_result.clone(),
-                        },
-                    ]
-                )
-                .await
-                .context("Failed to generate summary title")?
-                .into_iter()
-                .last()
-                .map(|m| m.text)
-                .unwrap_or_else(|| "Untitled Summary".to_string());
-
-            // Save to database
-            let summary_id = Uuid::new_v4();
-            sqlx::query(
-                "INSERT INTO summaries (id, title, language, summary, file_path) VALUES (?, ?, ?, ?, ?)",
-            )
-            .bind(&summary_id)
-            .bind(&summary_title)
-            .bind(language.code())
-            .bind(&summarize_result)
-            .bind(&file_path)
-            .execute(&mut *tx)
-            .await
-            .context("Failed to insert summary into database")?;
-
-            for segment in segments {
-                let transcript_id = Uuid::new_v4();
-                sqlx::query(
-                    "INSERT INTO summary_transcripts (id, summary_id, text, start_time, end_time) VALUES (?, ?, ?, ?, ?)"
-                )
-                .bind(&transcript_id)
-                .bind(&summary_id)
-                .bind(&segment.text)
-                .bind(segment.start)
-                .bind(segment.end)
-                .execute(&mut *tx)
-                .await
-                .context("Failed to insert summary transcript into database")?;
-            }
-
-            tx.commit()
-                .await
-                .context("Failed to commit database transaction")?;
-
-            // Fetch the inserted summary
-            let summary = sqlx::query_as::<_, Summary>("SELECT * FROM summaries WHERE id = ?")
-                .bind(&summary_id)
-                .fetch_one(&database)
-                .await
-                .context("Failed to fetch inserted summary")?;
-
-            // Step 4: Emit completion
-            emit_progress("Completed!", 4, Some(summary))?;
-
-            Ok::<(), anyhow::Error>(())
-        }
-        .await
-        {
-            error!(error = %e, "Error during summarization");
+    if start_time.is_some_and(|start| start < 0.0) {
+        return Err(ErrorCode::invalid_input("Start time must not be negative"));
+    }
+    if let Some(end) = end_time {
+        if end <= start_time.unwrap_or(0.0) {
+            return Err(ErrorCode::invalid_input(
+                "End time must be after the start time",
+            ));
         }
-    });
-
-    Ok(())
-}
-
-fn get_summary_prompt(language: Language) -> String {
-    format!(
-        "
-        You will receive an audio transcription generated by Whisper.
-        The transcription consists of multiple segments with the text attribute.
-        Ignore the timing information and focus only on the textual content.
-
-        *Transcription language:* {}
-
-        Assume this transcription is a *personal voice note recorded by the user*.
-        Your task is to transform the raw transcription into a *concise note that is easy to read and review later.*
-
-        *When summarizing:*
-        - Combine all segments into a coherent understanding
-        - Remove repetitions, filler words, and unimportant fragments
-        - Preserve the main ideas, thoughts, or information worth
+    }
 
-        *Output requirements*:
-        - Use Markdown format
-        - Use short paragraphs or bullet points when appropriate
-        - Do not include timestamps or segment references
+    let options = JobOptions {
+        transcription_mode: transcription_mode.unwrap_or_default(),
+        keep_original_transcript: keep_original_transcript.unwrap_or(false),
+        glossary_ids: glossary_ids.unwrap_or_default(),
+        audio_selection: audio_selection.unwrap_or_default(),
+        appended_files: appended_files.unwrap_or_default(),
+        start_time: start_time.filter(|start| *start > 0.0),
+        end_time,
+        ..JobOptions::new(language)
+    };
+    let job = state
+        .job_manager
+        .enqueue(file_path, options)
+        .await
+        .context("Failed to queue summarization job")?;
 
-        Write the summary in {} using clear, natural, and concise wording.
-        Do not add any information that is not present in the transcription.
-        ",
-        language.to_display_name(),
-        language.to_display_name()
-    )
+    Ok(job)
 }
 
-fn segments_to_text(segments: Vec<Segment>) -> String {
-    let content = segments
-        .iter()
-        .map(|s| s.text.trim())
-        .filter(|t| !t.is_empty())
-        .collect::<Vec<_>>()
-        .join("\n");
+#[tauri::command]
+pub async fn summarize_batch(
+    state: State<'_, AppState>,
+    language: Language,
+    paths: Vec<String>,
+    transcription_mode: Option<TranscriptionMode>,
+    keep_original_transcript: Option<bool>,
+    glossary_ids: Option<Vec<Uuid>>,
+    audio_selection: Option<AudioSelection>,
+) -> Result<BatchReport, ErrorCode> {
+    info!("Starting batch summarization for {} path(s)", paths.len());
+
+    let paths = paths.into_iter().map(PathBuf::from).collect();
+    let options = JobOptions {
+        transcription_mode: transcription_mode.unwrap_or_default(),
+        keep_original_transcript: keep_original_transcript.unwrap_or(false),
+        glossary_ids: glossary_ids.unwrap_or_default(),
+        audio_selection: audio_selection.unwrap_or_default(),
+        ..JobOptions::new(language)
+    };
+    let report = state
+        .job_manager
+        .enqueue_batch(paths, options)
+        .await
+        .context("Failed to queue batch summarization")?;
 
-    format!("<transcription>\n{}\n</transcription>", content)
+    Ok(report)
 }
@@ -0,0 +1,368 @@
+use std::{collections::BTreeMap, fs::File, path::Path};
+
+use anyhow::{bail, Context, Error, Result};
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{DecoderOptions, CODEC_TYPE_NULL},
+    errors::Error as SymphoniaError,
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::{MetadataOptions, MetadataRevision, StandardTagKey},
+    probe::{Hint, ProbeResult},
+};
+use tokio::sync::mpsc;
+
+use crate::features::summarize::{
+    audio::{AudioSelection, DecodeRange, CHUNK_SAMPLES, SAMPLE_RATE},
+    media::{AudioStreamInfo, MediaInfo},
+};
+
+/// File types the built-in decoder handles. Video containers and anything else need ffmpeg.
+pub const NATIVE_MIMETYPES: [&str; 5] = [
+    "audio/mpeg",
+    "audio/x-wav",
+    "audio/m4a",
+    "audio/ogg",
+    "audio/x-flac",
+];
+
+/// Taps on each side of the interpolated sample.
+const HALF_TAPS: usize = 16;
+
+/// Streaming windowed-sinc resampler. When downsampling, the filter cutoff follows the output
+/// rate, so frequencies above the new Nyquist are removed instead of aliased.
+pub struct Resampler {
+    /// Input samples per output sample
+    step: f64,
+    cutoff: f64,
+    /// Input samples not yet consumed, starting with the filter history
+    input: Vec<f32>,
+    /// Position of the next output sample in `input`
+    position: f64,
+    consumed: usize,
+    produced: usize,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32) -> Self {
+        let step = input_rate as f64 / output_rate as f64;
+        Resampler {
+            step,
+            cutoff: (1.0 / step).min(1.0),
+            input: vec![0.0; HALF_TAPS],
+            position: HALF_TAPS as f64,
+            consumed: 0,
+            produced: 0,
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32], output: &mut Vec<f32>) {
+        self.consumed += samples.len();
+        if self.step == 1.0 {
+            self.produced += samples.len();
+            output.extend_from_slice(samples);
+            return;
+        }
+
+        self.input.extend_from_slice(samples);
+        self.process(output);
+    }
+
+    /// Flushes the samples still held back for the filter.
+    pub fn finish(mut self, output: &mut Vec<f32>) {
+        if self.step == 1.0 {
+            return;
+        }
+
+        self.input.resize(self.input.len() + HALF_TAPS + 1, 0.0);
+        let expected = (self.consumed as f64 / self.step).ceil() as usize;
+        let start = output.len();
+        self.process(output);
+        // The zero padding may add one sample past the end of the input
+        let excess = self.produced.saturating_sub(expected);
+        output.truncate(output.len() - excess.min(output.len() - start));
+    }
+
+    fn process(&mut self, output: &mut Vec<f32>) {
+        while self.position + (HALF_TAPS as f64) < self.input.len() as f64 {
+            output.push(self.interpolate(self.position));
+            self.produced += 1;
+            self.position += self.step;
+        }
+
+        let drained = (self.position as usize).saturating_sub(HALF_TAPS);
+        self.input.drain(..drained);
+        self.position -= drained as f64;
+    }
+
+    fn interpolate(&self, position: f64) -> f32 {
+        let center = position as usize;
+        let mut sum = 0.0;
+        for index in center + 1 - HALF_TAPS..=center + HALF_TAPS {
+            let distance = position - index as f64;
+            if distance.abs() >= HALF_TAPS as f64 {
+                continue;
+            }
+
+            let x = std::f64::consts::PI * distance * self.cutoff;
+            let sinc = if x == 0.0 { 1.0 } else { x.sin() / x };
+            let window = 0.5 * (1.0 + (std::f64::consts::PI * distance / HALF_TAPS as f64).cos());
+            sum += self.input[index] as f64 * self.cutoff * sinc * window;
+        }
+
+        sum as f32
+    }
+}
+
+/// Decodes an audio track of a file with symphonia into mono 16 kHz chunks of
+/// [`CHUNK_SAMPLES`]. Only a single stream can be selected. Blocking, meant to run on its own
+/// thread; it stops early once the receiving side of `chunks` is dropped.
+pub fn decode_native(
+    path: &Path,
+    selection: &AudioSelection,
+    range: DecodeRange,
+    chunks: mpsc::Sender<Result<Vec<f32>>>,
+) {
+    if let Err(e) = decode_into(path, selection, range, &chunks) {
+        let _ = chunks.blocking_send(Err(e));
+    }
+}
+
+/// Averages the selected channels of a frame, or all of them when none are selected.
+fn downmix(frame: &[f32], channels: &[u16]) -> f32 {
+    if channels.is_empty() {
+        return frame.iter().sum::<f32>() / frame.len() as f32;
+    }
+
+    channels
+        .iter()
+        .filter_map(|channel| frame.get(usize::from(*channel)))
+        .sum::<f32>()
+        / channels.len() as f32
+}
+
+fn open_media(path: &Path) -> Result<ProbeResult> {
+    let file = File::open(path).context("Failed to open file")?;
+    let source = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    symphonia::default::get_probe()
+        .format(
+            &hint,
+            source,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .context("Unsupported audio format")
+}
+
+/// Reads the metadata of a file the built-in decoder supports. The file size is left at zero.
+pub fn probe_native(path: &Path) -> Result<MediaInfo> {
+    let mut probed = open_media(path)?;
+
+    let mut media = MediaInfo {
+        file_size: 0,
+        container: path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase()),
+        duration: None,
+        codecs: Vec::new(),
+        audio_streams: Vec::new(),
+        title: None,
+        recorded_at: None,
+        tags: BTreeMap::new(),
+    };
+
+    for (index, track) in probed.format.tracks().iter().enumerate() {
+        let params = &track.codec_params;
+        if params.codec == CODEC_TYPE_NULL {
+            continue;
+        }
+
+        let codec = symphonia::default::get_codecs()
+            .get_codec(params.codec)
+            .map(|descriptor| descriptor.short_name.to_string());
+        if let (None, Some(frames), Some(rate)) =
+            (media.duration, params.n_frames, params.sample_rate)
+        {
+            media.duration = Some(frames as f64 / rate as f64);
+        }
+        media.codecs.extend(codec.clone());
+        media.audio_streams.push(AudioStreamInfo {
+            index: index as u32,
+            codec,
+            sample_rate: params.sample_rate,
+            channels: params.channels.map(|channels| channels.count() as u16),
+            language: track.language.clone(),
+            title: None,
+        });
+    }
+
+    // Tags live in the container, or in front of it like ID3 does for MP3
+    let mut add_tags = |revision: &MetadataRevision| {
+        for tag in revision.tags() {
+            let value = tag.value.to_string();
+            match tag.std_key {
+                Some(StandardTagKey::TrackTitle) => media.title = Some(value.clone()),
+                Some(StandardTagKey::Date | StandardTagKey::OriginalDate) => {
+                    media.recorded_at.get_or_insert_with(|| value.clone());
+                }
+                _ => {}
+            }
+            media.tags.insert(tag.key.to_lowercase(), value);
+        }
+    };
+    if let Some(revision) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+        add_tags(revision);
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        add_tags(revision);
+    }
+
+    Ok(media)
+}
+
+fn decode_into(
+    path: &Path,
+    selection: &AudioSelection,
+    range: DecodeRange,
+    chunks: &mpsc::Sender<Result<Vec<f32>>>,
+) -> Result<()> {
+    let mut format = open_media(path)?.format;
+    // Stream indexes are positions in the track list, as reported by `probe_native`
+    let track = match selection.streams.as_slice() {
+        [] => format
+            .tracks()
+            .iter()
+            .find(|track| track.codec_params.codec != CODEC_TYPE_NULL),
+        [index] => format
+            .tracks()
+            .get(*index as usize)
+            .filter(|track| track.codec_params.codec != CODEC_TYPE_NULL),
+        _ => bail!("The built-in decoder cannot mix several audio streams"),
+    }
+    .context("File has no such audio track")?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .context("Unsupported audio codec")?;
+
+    let mut resampler: Option<Resampler> = None;
+    // Samples to skip and to keep at the source rate, known once the first packet is decoded
+    let mut trim: Option<(usize, Option<usize>)> = None;
+    let mut output = Vec::with_capacity(CHUNK_SAMPLES);
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break
+            }
+            Err(e) => return Err(Error::new(e).context("Failed to read audio packet")),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt packet is skipped, like ffmpeg does
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(Error::new(e).context("Failed to decode audio packet")),
+        };
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+
+        let mono = buffer
+            .samples()
+            .chunks_exact(channels)
+            .map(|frame| downmix(frame, &selection.channels))
+            .collect::<Vec<f32>>();
+        let rate = f64::from(spec.rate);
+        let (skip, remaining) = trim.get_or_insert_with(|| {
+            let skip = (range.start * rate) as usize;
+            let remaining = range
+                .end
+                .map(|end| ((end - range.start).max(0.0) * rate) as usize);
+            (skip, remaining)
+        });
+        let skipped = (*skip).min(mono.len());
+        *skip -= skipped;
+        let mut mono = &mono[skipped..];
+        if let Some(remaining) = remaining.as_mut() {
+            mono = &mono[..(*remaining).min(mono.len())];
+            *remaining -= mono.len();
+        }
+        resampler
+            .get_or_insert_with(|| Resampler::new(spec.rate, SAMPLE_RATE as u32))
+            .push(mono, &mut output);
+
+        while output.len() >= CHUNK_SAMPLES {
+            let rest = output.split_off(CHUNK_SAMPLES);
+            if chunks.blocking_send(Ok(output)).is_err() {
+                return Ok(());
+            }
+            output = rest;
+        }
+        if *remaining == Some(0) {
+            break;
+        }
+    }
+
+    if let Some(resampler) = resampler {
+        resampler.finish(&mut output);
+    }
+    for chunk in output.chunks(CHUNK_SAMPLES) {
+        if chunks.blocking_send(Ok(chunk.to_vec())).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frequency: f64, rate: u32, length: usize) -> Vec<f32> {
+        (0..length)
+            .map(|i| (i as f64 * frequency * std::f64::consts::TAU / rate as f64).sin() as f32)
+            .collect()
+    }
+
+    #[test]
+    fn test_resampler_downsamples() {
+        let input = sine(440.0, 44100, 44100);
+        let mut resampler = Resampler::new(44100, 16000);
+        let mut output = Vec::new();
+        for chunk in input.chunks(1000) {
+            resampler.push(chunk, &mut output);
+        }
+        resampler.finish(&mut output);
+
+        assert_eq!(output.len(), 16000);
+        let expected = sine(440.0, 16000, 16000);
+        for (a, b) in output[100..15900].iter().zip(&expected[100..15900]) {
+            assert!((a - b).abs() < 0.01, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_resampler_filters_above_nyquist() {
+        let input = sine(12000.0, 48000, 48000);
+        let mut resampler = Resampler::new(48000, 16000);
+        let mut output = Vec::new();
+        resampler.push(&input, &mut output);
+        resampler.finish(&mut output);
+
+        let peak = output[100..15900]
+            .iter()
+            .fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(peak < 0.1, "aliased peak {}", peak);
+    }
+}
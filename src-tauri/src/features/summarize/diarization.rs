@@ -0,0 +1,493 @@
+use std::f32::consts::PI;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use crate::features::{model::speech_to_text::Segment, summarize::audio::SAMPLE_RATE};
+
+/// 25 ms analysis window with a 10 ms hop, the usual setup for speech features.
+const FRAME_SIZE: usize = SAMPLE_RATE / 40;
+const HOP_SIZE: usize = SAMPLE_RATE / 100;
+const FFT_SIZE: usize = 512;
+const MEL_FILTERS: usize = 26;
+/// Cepstral coefficients 1..=12. The 0th one only carries loudness and is skipped.
+const FEATURES: usize = 12;
+/// Segments shorter than this are too short to tell voices apart and inherit a neighbour's speaker.
+const MIN_SEGMENT_FRAMES: usize = 100;
+/// Weight of the model complexity penalty in the BIC merge criterion.
+const BIC_PENALTY: f64 = 2.0;
+const VARIANCE_FLOOR: f64 = 1e-3;
+/// Voices are compared on at most this many frames, so a long recording does not outweigh a
+/// short enrollment in the BIC penalty.
+const MAX_COMPARED_FRAMES: f64 = 3000.0;
+
+/// Labels every segment with a speaker ("Speaker 1", "Speaker 2", ...) in order of appearance.
+///
+/// Each segment is described by the MFCC frames of its audio and modelled as a diagonal
+/// Gaussian. Segments are then merged bottom-up for as long as the Bayesian information
+/// criterion prefers one shared voice model over two separate ones, so no speaker count or
+/// distance threshold has to be picked up front.
+///
+/// Returns the voice of every speaker, where index 0 belongs to "Speaker 1".
+pub fn assign_speakers(
+    samples: &[f32],
+    segments: &mut [Segment],
+    cancellation_token: &CancellationToken,
+) -> Result<Vec<VoiceEmbedding>> {
+    let voices = segment_voices(samples, segments);
+    if cancellation_token.is_cancelled() {
+        bail!("Speaker diarization was cancelled");
+    }
+
+    cluster_speakers(voices, segments, cancellation_token)
+}
+
+/// Voice of every segment, `None` for segments too short to tell voices apart. `samples` is
+/// the audio the segment times refer to, so a long recording can be described window by
+/// window and clustered once with [`cluster_speakers`].
+pub fn segment_voices(samples: &[f32], segments: &[Segment]) -> Vec<Option<VoiceEmbedding>> {
+    let features = mfcc(samples);
+    segments
+        .iter()
+        .map(|segment| {
+            let first = (segment.start.max(0.0) * 100.0) as usize;
+            // Only frames that end inside the segment, so the next speaker does not leak in
+            let end = (segment.end.max(0.0) * SAMPLE_RATE as f64) as usize;
+            let last = (end.saturating_sub(FRAME_SIZE) / HOP_SIZE + 1).min(features.len());
+            if last <= first || last - first < MIN_SEGMENT_FRAMES {
+                return None;
+            }
+
+            Some(VoiceEmbedding::from_frames(&features[first..last]))
+        })
+        .collect()
+}
+
+/// Labels the segments with the speakers found among their voices, see [`assign_speakers`].
+pub fn cluster_speakers(
+    voices: Vec<Option<VoiceEmbedding>>,
+    segments: &mut [Segment],
+    cancellation_token: &CancellationToken,
+) -> Result<Vec<VoiceEmbedding>> {
+    let mut clusters: Vec<VoiceEmbedding> = Vec::new();
+    let mut segment_clusters: Vec<Option<usize>> = vec![None; segments.len()];
+    for (index, cluster) in voices.into_iter().enumerate() {
+        let Some(cluster) = cluster else {
+            continue;
+        };
+        // Consecutive segments are usually the same person, so try the previous cluster first
+        match clusters.last_mut() {
+            Some(previous) if delta_bic(previous, &cluster) < 0.0 => previous.merge(&cluster),
+            _ => clusters.push(cluster),
+        }
+        segment_clusters[index] = Some(clusters.len() - 1);
+    }
+
+    // Deltas between every pair of clusters, only the row of a merged cluster changes after a
+    // merge. Recomputing all of them would be cubic in the number of clusters.
+    let mut deltas = vec![vec![f64::INFINITY; clusters.len()]; clusters.len()];
+    for a in 0..clusters.len() {
+        if cancellation_token.is_cancelled() {
+            bail!("Speaker diarization was cancelled");
+        }
+        for b in a + 1..clusters.len() {
+            deltas[a][b] = delta_bic(&clusters[a], &clusters[b]);
+        }
+    }
+
+    let mut labels = (0..clusters.len()).collect::<Vec<usize>>();
+    let mut active = (0..clusters.len()).collect::<Vec<usize>>();
+    while active.len() > 1 {
+        if cancellation_token.is_cancelled() {
+            bail!("Speaker diarization was cancelled");
+        }
+
+        let mut best: Option<(usize, usize, f64)> = None;
+        for (i, &a) in active.iter().enumerate() {
+            for &b in &active[i + 1..] {
+                let delta = deltas[a][b];
+                if best.is_none_or(|(_, _, best_delta)| delta < best_delta) {
+                    best = Some((a, b, delta));
+                }
+            }
+        }
+
+        let Some((a, b, _)) = best.filter(|(_, _, delta)| *delta < 0.0) else {
+            break;
+        };
+        let merged = clusters[b].clone();
+        clusters[a].merge(&merged);
+        active.retain(|&cluster| cluster != b);
+        labels.iter_mut().filter(|l| **l == b).for_each(|l| *l = a);
+
+        for &other in &active {
+            if other != a {
+                let delta = delta_bic(&clusters[a], &clusters[other]);
+                deltas[a.min(other)][a.max(other)] = delta;
+            }
+        }
+    }
+
+    // Number speakers by their first appearance
+    let mut order: Vec<usize> = Vec::new();
+    let speakers = segment_clusters
+        .iter()
+        .map(|cluster| {
+            cluster.map(|cluster| {
+                let label = labels[cluster];
+                match order.iter().position(|l| *l == label) {
+                    Some(position) => position + 1,
+                    None => {
+                        order.push(label);
+                        order.len()
+                    }
+                }
+            })
+        })
+        .collect::<Vec<Option<usize>>>();
+
+    let mut previous = speakers.iter().flatten().next().copied();
+    for (segment, speaker) in segments.iter_mut().zip(speakers) {
+        let speaker = speaker.or(previous);
+        previous = speaker;
+        segment.speaker = speaker.map(speaker_label);
+    }
+
+    Ok(order.iter().map(|label| clusters[*label].clone()).collect())
+}
+
+pub fn speaker_label(number: usize) -> String {
+    format!("Speaker {}", number)
+}
+
+/// Gives every segment in `to` the speaker of the segment in `from` it overlaps the most, so a
+/// second transcript of the same audio uses the same labels.
+pub fn copy_speakers(from: &[Segment], to: &mut [Segment]) {
+    for segment in to.iter_mut() {
+        segment.speaker = from
+            .iter()
+            .map(|source| {
+                let overlap = source.end.min(segment.end) - source.start.max(segment.start);
+                (source, overlap)
+            })
+            .filter(|(_, overlap)| *overlap > 0.0)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .and_then(|(source, _)| source.speaker.clone());
+    }
+}
+
+/// Sufficient statistics of a diagonal Gaussian over MFCC frames. Used for the clusters during
+/// diarization and stored as the voice of a speaker to recognize them in later recordings.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VoiceEmbedding {
+    count: f64,
+    sum: [f64; FEATURES],
+    sum_squares: [f64; FEATURES],
+}
+
+impl VoiceEmbedding {
+    fn from_frames(frames: &[[f32; FEATURES]]) -> Self {
+        let mut cluster = VoiceEmbedding {
+            count: 0.0,
+            sum: [0.0; FEATURES],
+            sum_squares: [0.0; FEATURES],
+        };
+        for frame in frames {
+            cluster.count += 1.0;
+            for (k, value) in frame.iter().enumerate() {
+                cluster.sum[k] += f64::from(*value);
+                cluster.sum_squares[k] += f64::from(*value).powi(2);
+            }
+        }
+
+        cluster
+    }
+
+    pub fn merge(&mut self, other: &VoiceEmbedding) {
+        self.count += other.count;
+        for k in 0..FEATURES {
+            self.sum[k] += other.sum[k];
+            self.sum_squares[k] += other.sum_squares[k];
+        }
+    }
+
+    /// Index of the voice in `voices` that most likely belongs to the same person, if any.
+    pub fn closest(&self, voices: &[VoiceEmbedding]) -> Option<usize> {
+        let this = self.capped();
+        voices
+            .iter()
+            .map(|voice| delta_bic(&this, &voice.capped()))
+            .enumerate()
+            .filter(|(_, delta)| *delta < 0.0)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    }
+
+    fn capped(&self) -> VoiceEmbedding {
+        let scale = (MAX_COMPARED_FRAMES / self.count).min(1.0);
+        VoiceEmbedding {
+            count: self.count * scale,
+            sum: self.sum.map(|sum| sum * scale),
+            sum_squares: self.sum_squares.map(|sum| sum * scale),
+        }
+    }
+
+    fn log_det(&self) -> f64 {
+        (0..FEATURES)
+            .map(|k| {
+                let mean = self.sum[k] / self.count;
+                let variance = self.sum_squares[k] / self.count - mean * mean;
+                variance.max(VARIANCE_FLOOR).ln()
+            })
+            .sum()
+    }
+}
+
+/// BIC gain of modelling two clusters as one voice. Negative means they are the same speaker.
+fn delta_bic(a: &VoiceEmbedding, b: &VoiceEmbedding) -> f64 {
+    let mut merged = a.clone();
+    merged.merge(b);
+
+    let parameters = 2.0 * FEATURES as f64;
+    let penalty = 0.5 * parameters * merged.count.ln();
+
+    0.5 * (merged.count * merged.log_det() - a.count * a.log_det() - b.count * b.log_det())
+        - BIC_PENALTY * penalty
+}
+
+/// Mel-frequency cepstral coefficients, one frame every 10 ms.
+fn mfcc(samples: &[f32]) -> Vec<[f32; FEATURES]> {
+    let window = (0..FRAME_SIZE)
+        .map(|i| 0.54 - 0.46 * (2.0 * PI * i as f32 / (FRAME_SIZE - 1) as f32).cos())
+        .collect::<Vec<f32>>();
+    let filters = mel_filterbank();
+
+    let frame_count = samples.len().saturating_sub(FRAME_SIZE) / HOP_SIZE + 1;
+    let mut features = Vec::with_capacity(frame_count);
+    let mut real = vec![0.0f32; FFT_SIZE];
+    let mut imaginary = vec![0.0f32; FFT_SIZE];
+
+    for frame in 0..frame_count {
+        let start = frame * HOP_SIZE;
+        let frame_samples = &samples[start..(start + FRAME_SIZE).min(samples.len())];
+        real.fill(0.0);
+        imaginary.fill(0.0);
+        for (i, sample) in frame_samples.iter().enumerate() {
+            real[i] = sample * window[i];
+        }
+        fft(&mut real, &mut imaginary);
+
+        let power = (0..=FFT_SIZE / 2)
+            .map(|bin| real[bin] * real[bin] + imaginary[bin] * imaginary[bin])
+            .collect::<Vec<f32>>();
+        let energies = filters
+            .iter()
+            .map(|filter| {
+                let energy = filter
+                    .iter()
+                    .map(|(bin, weight)| power[*bin] * weight)
+                    .sum::<f32>();
+                energy.max(1e-10).ln()
+            })
+            .collect::<Vec<f32>>();
+
+        let mut coefficients = [0.0f32; FEATURES];
+        for (k, coefficient) in coefficients.iter_mut().enumerate() {
+            let k = k + 1;
+            *coefficient = energies
+                .iter()
+                .enumerate()
+                .map(|(m, energy)| {
+                    energy * (PI * k as f32 * (m as f32 + 0.5) / MEL_FILTERS as f32).cos()
+                })
+                .sum::<f32>()
+                * (2.0 / MEL_FILTERS as f32).sqrt();
+        }
+        features.push(coefficients);
+    }
+
+    features
+}
+
+/// Triangular filters spaced evenly on the mel scale, as `(fft bin, weight)` pairs.
+fn mel_filterbank() -> Vec<Vec<(usize, f32)>> {
+    let to_mel = |hz: f32| 2595.0 * (1.0 + hz / 700.0).log10();
+    let to_hz = |mel: f32| 700.0 * (10f32.powf(mel / 2595.0) - 1.0);
+
+    let (low, high) = (to_mel(20.0), to_mel(SAMPLE_RATE as f32 / 2.0 - 400.0));
+    let bins = (0..MEL_FILTERS + 2)
+        .map(|i| {
+            let hz = to_hz(low + (high - low) * i as f32 / (MEL_FILTERS + 1) as f32);
+            hz * FFT_SIZE as f32 / SAMPLE_RATE as f32
+        })
+        .collect::<Vec<f32>>();
+
+    (0..MEL_FILTERS)
+        .map(|m| {
+            let (left, center, right) = (bins[m], bins[m + 1], bins[m + 2]);
+            (left.ceil() as usize..=right.floor() as usize)
+                .filter_map(|bin| {
+                    let position = bin as f32;
+                    let weight = if position <= center {
+                        (position - left) / (center - left)
+                    } else {
+                        (right - position) / (right - center)
+                    };
+                    (weight > 0.0).then_some((bin, weight))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// In-place iterative radix-2 FFT. The length must be a power of two.
+fn fft(real: &mut [f32], imaginary: &mut [f32]) {
+    let n = real.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            real.swap(i, j);
+            imaginary.swap(i, j);
+        }
+    }
+
+    let mut length = 2;
+    while length <= n {
+        let angle = -2.0 * PI / length as f32;
+        for start in (0..n).step_by(length) {
+            for k in 0..length / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + length / 2);
+                let t_real = real[b] * cos - imaginary[b] * sin;
+                let t_imaginary = real[b] * sin + imaginary[b] * cos;
+                real[b] = real[a] - t_real;
+                imaginary[b] = imaginary[a] - t_imaginary;
+                real[a] += t_real;
+                imaginary[a] += t_imaginary;
+            }
+        }
+        length <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Harmonic "voice" with a fixed pitch and some deterministic noise.
+    fn voice(pitch: f32, seconds: usize, seed: u32) -> Vec<f32> {
+        let mut state = seed;
+        (0..seconds * SAMPLE_RATE)
+            .map(|i| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let noise = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+                let t = i as f32 / SAMPLE_RATE as f32;
+                (1..=5)
+                    .map(|h| (2.0 * PI * pitch * h as f32 * t).sin() / h as f32)
+                    .sum::<f32>()
+                    * 0.2
+                    + noise * 0.02
+            })
+            .collect()
+    }
+
+    fn segments(count: usize, seconds: usize) -> Vec<Segment> {
+        (0..count)
+            .map(|i| Segment {
+                text: String::new(),
+                start: (i * seconds) as f64,
+                end: ((i + 1) * seconds) as f64,
+                words: Vec::new(),
+                speaker: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fft() {
+        let mut real = vec![0.0, 1.0, 0.0, -1.0];
+        let mut imaginary = vec![0.0; 4];
+
+        fft(&mut real, &mut imaginary);
+
+        assert!((imaginary[1] + 2.0).abs() < 1e-6);
+        assert!((imaginary[3] - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_assign_speakers_two_voices() {
+        let mut samples = voice(120.0, 4, 1);
+        samples.extend(voice(800.0, 4, 2));
+        samples.extend(voice(120.0, 4, 3));
+        let mut segments = segments(3, 4);
+
+        assign_speakers(&samples, &mut segments, &CancellationToken::new()).unwrap();
+
+        let speakers = segments
+            .iter()
+            .map(|s| s.speaker.clone().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(speakers, vec!["Speaker 1", "Speaker 2", "Speaker 1"]);
+    }
+
+    #[test]
+    fn test_cluster_speakers_across_windows() {
+        // Each window is described on its own audio, the speakers are found across both
+        let mut voices = segment_voices(&voice(120.0, 4, 1), &segments(1, 4));
+        let mut second = voice(800.0, 4, 2);
+        second.extend(voice(120.0, 4, 3));
+        voices.extend(segment_voices(&second, &segments(2, 4)));
+        let mut segments = segments(3, 4);
+
+        cluster_speakers(voices, &mut segments, &CancellationToken::new()).unwrap();
+
+        let speakers = segments
+            .iter()
+            .map(|s| s.speaker.clone().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(speakers, vec!["Speaker 1", "Speaker 2", "Speaker 1"]);
+    }
+
+    #[test]
+    fn test_assign_speakers_one_voice() {
+        let samples = voice(150.0, 12, 1);
+        let mut segments = segments(3, 4);
+
+        assign_speakers(&samples, &mut segments, &CancellationToken::new()).unwrap();
+
+        assert!(segments
+            .iter()
+            .all(|s| s.speaker.as_deref() == Some("Speaker 1")));
+    }
+
+    #[test]
+    fn test_closest_voice() {
+        let mut low = segments(1, 4);
+        let mut high = segments(1, 4);
+        let enrolled = [
+            assign_speakers(&voice(120.0, 4, 1), &mut low, &CancellationToken::new())
+                .unwrap()
+                .remove(0),
+            assign_speakers(&voice(800.0, 4, 2), &mut high, &CancellationToken::new())
+                .unwrap()
+                .remove(0),
+        ];
+
+        let mut samples = voice(800.0, 4, 3);
+        samples.extend(voice(120.0, 4, 4));
+        let mut recording = segments(2, 4);
+        let voices = assign_speakers(&samples, &mut recording, &CancellationToken::new()).unwrap();
+
+        assert_eq!(voices[0].closest(&enrolled), Some(1));
+        assert_eq!(voices[1].closest(&enrolled), Some(0));
+        assert_eq!(voices[0].closest(&enrolled[..1]), None);
+    }
+}
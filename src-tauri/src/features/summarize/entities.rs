@@ -1,14 +1,52 @@
 use chrono::NaiveDateTime;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 use uuid::Uuid;
 
+use crate::{
+    features::{
+        model::speech_to_text::{SpeechToTextModel, TranscriptionMode, TranscriptionSettings},
+        summarize::{audio::AudioSelection, media::MediaInfo},
+    },
+    utils::sqlite::impl_sqlite_json,
+};
+
+/// Input file of a summary, placed on the timeline of all its files played in order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceFile {
+    pub path: String,
+    /// Start of the file on the combined timeline in seconds, known once it was decoded
+    pub offset: Option<f64>,
+    pub duration: Option<f64>,
+}
+
+/// Input files of a summary in recording order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SourceFiles(pub Vec<SourceFile>);
+
+impl_sqlite_json!(SourceFiles);
+
 #[derive(Clone, FromRow, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Summary {
     pub id: Uuid,
     pub title: String,
     pub language: String,
+    /// Set when the language was detected automatically
+    pub language_confidence: Option<f64>,
+    pub transcription_mode: TranscriptionMode,
+    /// Missing for summaries created before the settings were recorded
+    pub speech_to_text_model: Option<SpeechToTextModel>,
+    pub transcription_settings: Option<TranscriptionSettings>,
+    pub media_info: Option<MediaInfo>,
+    pub audio_selection: Option<AudioSelection>,
+    /// Input files with their place on the combined timeline
+    pub sources: Option<SourceFiles>,
+    /// Transcribed range of the combined timeline, in seconds
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
     pub summary: String,
     pub file_path: String,
     pub created_at: NaiveDateTime,
@@ -16,11 +54,36 @@ pub struct Summary {
 }
 
 #[derive(FromRow, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SummaryTranscript {
     pub id: Uuid,
     pub summary_id: Uuid,
     pub text: String,
     pub start_time: f64,
     pub end_time: f64,
+    /// Segment in the spoken language, kept next to a translated transcript
+    pub is_original: bool,
+    /// Label assigned by diarization, e.g. "Speaker 1"
+    pub speaker: Option<String>,
     pub created_at: NaiveDateTime,
 }
+
+#[derive(FromRow, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptWord {
+    pub id: Uuid,
+    pub transcript_id: Uuid,
+    pub position: i64,
+    pub text: String,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub probability: f64,
+}
+
+/// Transcript segment together with its words, as returned to the frontend.
+#[derive(Serialize)]
+pub struct TranscriptSegment {
+    #[serde(flatten)]
+    pub transcript: SummaryTranscript,
+    pub words: Vec<TranscriptWord>,
+}
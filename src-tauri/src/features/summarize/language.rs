@@ -7,33 +7,153 @@ use sqlx::{
 };
 use strum_macros::EnumIter;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, EnumIter)]
-pub enum Language {
-    #[serde(rename = "en")]
-    EnUs,
-    #[serde(rename = "id")]
-    IdId,
-    #[serde(rename = "ja")]
-    JaJp,
-}
+/// Declares the `Language` enum from whisper.cpp's language table, in the same order.
+/// Each entry maps a variant to its ISO 639-1 code (as used by whisper) and native name.
+macro_rules! languages {
+    ($($variant:ident => ($code:literal, $name:literal)),+ $(,)?) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
+        pub enum Language {
+            /// Detect the spoken language from the recording
+            #[serde(rename = "auto")]
+            Auto,
+            $(
+                #[serde(rename = $code)]
+                $variant,
+            )+
+        }
+
+        impl Language {
+            pub fn to_display_name(&self) -> &'static str {
+                match self {
+                    Language::Auto => "Auto-detect",
+                    $(Language::$variant => $name,)+
+                }
+            }
 
-impl Language {
-    pub fn to_display_name(&self) -> &'static str {
-        match self {
-            Language::EnUs => "English (US)",
-            Language::IdId => "Bahasa Indonesia",
-            Language::JaJp => "日本語",
+            // return ISO 639-1 code
+            pub fn code(&self) -> &'static str {
+                match self {
+                    Language::Auto => "auto",
+                    $(Language::$variant => $code,)+
+                }
+            }
         }
-    }
 
-    // return ISO 639-1 code
-    pub fn code(&self) -> &'static str {
-        match self {
-            Language::EnUs => "en",
-            Language::IdId => "id",
-            Language::JaJp => "ja",
+        impl FromStr for Language {
+            type Err = ();
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    "auto" => Ok(Language::Auto),
+                    $($code => Ok(Language::$variant),)+
+                    _ => Err(()),
+                }
+            }
         }
-    }
+    };
+}
+
+languages! {
+    English => ("en", "English"),
+    Chinese => ("zh", "中文"),
+    German => ("de", "Deutsch"),
+    Spanish => ("es", "Español"),
+    Russian => ("ru", "Русский"),
+    Korean => ("ko", "한국어"),
+    French => ("fr", "Français"),
+    Japanese => ("ja", "日本語"),
+    Portuguese => ("pt", "Português"),
+    Turkish => ("tr", "Türkçe"),
+    Polish => ("pl", "Polski"),
+    Catalan => ("ca", "Català"),
+    Dutch => ("nl", "Nederlands"),
+    Arabic => ("ar", "العربية"),
+    Swedish => ("sv", "Svenska"),
+    Italian => ("it", "Italiano"),
+    Indonesian => ("id", "Bahasa Indonesia"),
+    Hindi => ("hi", "हिन्दी"),
+    Finnish => ("fi", "Suomi"),
+    Vietnamese => ("vi", "Tiếng Việt"),
+    Hebrew => ("he", "עברית"),
+    Ukrainian => ("uk", "Українська"),
+    Greek => ("el", "Ελληνικά"),
+    Malay => ("ms", "Bahasa Melayu"),
+    Czech => ("cs", "Čeština"),
+    Romanian => ("ro", "Română"),
+    Danish => ("da", "Dansk"),
+    Hungarian => ("hu", "Magyar"),
+    Tamil => ("ta", "தமிழ்"),
+    Norwegian => ("no", "Norsk"),
+    Thai => ("th", "ไทย"),
+    Urdu => ("ur", "اردو"),
+    Croatian => ("hr", "Hrvatski"),
+    Bulgarian => ("bg", "Български"),
+    Lithuanian => ("lt", "Lietuvių"),
+    Latin => ("la", "Latina"),
+    Maori => ("mi", "Māori"),
+    Malayalam => ("ml", "മലയാളം"),
+    Welsh => ("cy", "Cymraeg"),
+    Slovak => ("sk", "Slovenčina"),
+    Telugu => ("te", "తెలుగు"),
+    Persian => ("fa", "فارسی"),
+    Latvian => ("lv", "Latviešu"),
+    Bengali => ("bn", "বাংলা"),
+    Serbian => ("sr", "Српски"),
+    Azerbaijani => ("az", "Azərbaycanca"),
+    Slovenian => ("sl", "Slovenščina"),
+    Kannada => ("kn", "ಕನ್ನಡ"),
+    Estonian => ("et", "Eesti"),
+    Macedonian => ("mk", "Македонски"),
+    Breton => ("br", "Brezhoneg"),
+    Basque => ("eu", "Euskara"),
+    Icelandic => ("is", "Íslenska"),
+    Armenian => ("hy", "Հայերեն"),
+    Nepali => ("ne", "नेपाली"),
+    Mongolian => ("mn", "Монгол"),
+    Bosnian => ("bs", "Bosanski"),
+    Kazakh => ("kk", "Қазақ"),
+    Albanian => ("sq", "Shqip"),
+    Swahili => ("sw", "Kiswahili"),
+    Galician => ("gl", "Galego"),
+    Marathi => ("mr", "मराठी"),
+    Punjabi => ("pa", "ਪੰਜਾਬੀ"),
+    Sinhala => ("si", "සිංහල"),
+    Khmer => ("km", "ខ្មែរ"),
+    Shona => ("sn", "ChiShona"),
+    Yoruba => ("yo", "Yorùbá"),
+    Somali => ("so", "Soomaali"),
+    Afrikaans => ("af", "Afrikaans"),
+    Occitan => ("oc", "Occitan"),
+    Georgian => ("ka", "ქართული"),
+    Belarusian => ("be", "Беларуская"),
+    Tajik => ("tg", "Тоҷикӣ"),
+    Sindhi => ("sd", "سنڌي"),
+    Gujarati => ("gu", "ગુજરાતી"),
+    Amharic => ("am", "አማርኛ"),
+    Yiddish => ("yi", "ייִדיש"),
+    Lao => ("lo", "ລາວ"),
+    Uzbek => ("uz", "Oʻzbekcha"),
+    Faroese => ("fo", "Føroyskt"),
+    HaitianCreole => ("ht", "Kreyòl ayisyen"),
+    Pashto => ("ps", "پښتو"),
+    Turkmen => ("tk", "Türkmençe"),
+    Nynorsk => ("nn", "Nynorsk"),
+    Maltese => ("mt", "Malti"),
+    Sanskrit => ("sa", "संस्कृतम्"),
+    Luxembourgish => ("lb", "Lëtzebuergesch"),
+    Myanmar => ("my", "မြန်မာ"),
+    Tibetan => ("bo", "བོད་ཡིག"),
+    Tagalog => ("tl", "Tagalog"),
+    Malagasy => ("mg", "Malagasy"),
+    Assamese => ("as", "অসমীয়া"),
+    Tatar => ("tt", "Татарча"),
+    Hawaiian => ("haw", "ʻŌlelo Hawaiʻi"),
+    Lingala => ("ln", "Lingála"),
+    Hausa => ("ha", "Hausa"),
+    Bashkir => ("ba", "Башҡортса"),
+    Javanese => ("jw", "Basa Jawa"),
+    Sundanese => ("su", "Basa Sunda"),
+    Cantonese => ("yue", "粵語"),
 }
 
 #[derive(Serialize)]
@@ -59,19 +179,6 @@ impl fmt::Display for Language {
     }
 }
 
-impl FromStr for Language {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "en" => Ok(Language::EnUs),
-            "id" => Ok(Language::IdId),
-            "ja" => Ok(Language::JaJp),
-            _ => Err(()),
-        }
-    }
-}
-
 impl Type<Sqlite> for Language {
     fn type_info() -> sqlx::sqlite::SqliteTypeInfo {
         <String as Type<Sqlite>>::type_info()
@@ -94,3 +201,36 @@ impl<'q> Encode<'q, Sqlite> for Language {
         <String as Encode<Sqlite>>::encode(s, args)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::SqlitePool;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_existing_codes_are_stable() {
+        assert_eq!(Language::from_str("en"), Ok(Language::English));
+        assert_eq!(Language::from_str("id"), Ok(Language::Indonesian));
+        assert_eq!(Language::from_str("ja"), Ok(Language::Japanese));
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_round_trip() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+
+        for language in Language::iter() {
+            let decoded: Language = sqlx::query_scalar("SELECT ?")
+                .bind(language)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+            assert_eq!(decoded, language);
+            assert_eq!(
+                serde_json::to_value(language).unwrap(),
+                serde_json::Value::from(language.code())
+            );
+        }
+    }
+}
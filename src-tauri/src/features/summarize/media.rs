@@ -0,0 +1,225 @@
+use std::{collections::BTreeMap, path::PathBuf, process::Stdio};
+
+use anyhow::{Context, Error, Result};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::{
+    features::summarize::{
+        audio::{detect_mime_type, get_ffprobe_path},
+        decoder::{probe_native, NATIVE_MIMETYPES},
+    },
+    utils::sqlite::impl_sqlite_json,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioStreamInfo {
+    /// Index of the stream within the file, as used by ffmpeg
+    pub index: u32,
+    pub codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    pub language: Option<String>,
+    pub title: Option<String>,
+}
+
+/// Metadata of a media file, read without decoding it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaInfo {
+    pub file_size: u64,
+    /// Container format, e.g. "mov,mp4,m4a,3gp,3g2,mj2" or "wav"
+    pub container: Option<String>,
+    /// Duration in seconds
+    pub duration: Option<f64>,
+    /// Codecs of every stream in the file, video included
+    pub codecs: Vec<String>,
+    pub audio_streams: Vec<AudioStreamInfo>,
+    pub title: Option<String>,
+    /// Recording date as written by the recorder, usually ISO 8601
+    pub recorded_at: Option<String>,
+    /// Every tag of the file, with lowercase keys
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+}
+
+impl_sqlite_json!(MediaInfo);
+
+impl MediaInfo {
+    /// Fills `title` and `recorded_at` from the common tag names.
+    fn with_tags(mut self, tags: BTreeMap<String, String>) -> Self {
+        let tag = |keys: &[&str]| keys.iter().find_map(|key| tags.get(*key).cloned());
+        self.title = self.title.or_else(|| tag(&["title"]));
+        self.recorded_at = self.recorded_at.or_else(|| {
+            tag(&[
+                "com.apple.quicktime.creationdate",
+                "creation_time",
+                "date",
+                "date_recorded",
+            ])
+        });
+        self.tags = tags;
+        self
+    }
+}
+
+#[derive(Deserialize)]
+struct FfprobeOutput {
+    #[serde(default)]
+    streams: Vec<FfprobeStream>,
+    format: FfprobeFormat,
+}
+
+#[derive(Deserialize)]
+struct FfprobeStream {
+    index: u32,
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    sample_rate: Option<String>,
+    channels: Option<u16>,
+    #[serde(default)]
+    tags: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct FfprobeFormat {
+    format_name: Option<String>,
+    duration: Option<String>,
+    #[serde(default)]
+    tags: BTreeMap<String, String>,
+}
+
+fn parse_ffprobe(output: &[u8], file_size: u64) -> Result<MediaInfo> {
+    let output: FfprobeOutput =
+        serde_json::from_slice(output).context("Failed to parse ffprobe output")?;
+    let lowercase = |tags: BTreeMap<String, String>| {
+        tags.into_iter()
+            .map(|(key, value)| (key.to_lowercase(), value))
+            .collect::<BTreeMap<String, String>>()
+    };
+
+    let codecs = output
+        .streams
+        .iter()
+        .filter_map(|stream| stream.codec_name.clone())
+        .collect();
+    let audio_streams = output
+        .streams
+        .into_iter()
+        .filter(|stream| stream.codec_type.as_deref() == Some("audio"))
+        .map(|stream| {
+            let tags = lowercase(stream.tags);
+            AudioStreamInfo {
+                index: stream.index,
+                codec: stream.codec_name,
+                sample_rate: stream.sample_rate.and_then(|rate| rate.parse().ok()),
+                channels: stream.channels,
+                language: tags.get("language").cloned(),
+                title: tags.get("title").cloned(),
+            }
+        })
+        .collect();
+
+    let media = MediaInfo {
+        file_size,
+        container: output.format.format_name,
+        duration: output
+            .format
+            .duration
+            .and_then(|duration| duration.parse().ok()),
+        codecs,
+        audio_streams,
+        title: None,
+        recorded_at: None,
+        tags: BTreeMap::new(),
+    };
+
+    Ok(media.with_tags(lowercase(output.format.tags)))
+}
+
+/// Reads the metadata of a media file with ffprobe, or with the built-in decoder when ffprobe
+/// cannot be found and the format is one it supports.
+pub async fn probe_media(path: &PathBuf) -> Result<MediaInfo> {
+    let mime_type = detect_mime_type(path).await?;
+    let file_size = tokio::fs::metadata(path)
+        .await
+        .context("Failed to read file metadata")?
+        .len();
+
+    let ffprobe_path = get_ffprobe_path();
+    let output = Command::new(&ffprobe_path)
+        .args(&[
+            "-v",
+            "error",
+            "-print_format",
+            "json",
+            "-show_format",
+            "-show_streams",
+        ])
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .await;
+
+    match output {
+        Ok(output) if output.status.success() => parse_ffprobe(&output.stdout, file_size),
+        Ok(output) => Err(Error::msg(format!(
+            "ffprobe command failed with error: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if !NATIVE_MIMETYPES.contains(&mime_type) {
+                return Err(Error::msg(format!(
+                    "ffprobe was not found at {}, it is required to read {} files",
+                    ffprobe_path.display(),
+                    mime_type
+                )));
+            }
+
+            let path = path.clone();
+            let mut media = tokio::task::spawn_blocking(move || probe_native(&path))
+                .await
+                .context("Media probing thread panicked")??;
+            let tags = std::mem::take(&mut media.tags);
+            Ok(MediaInfo { file_size, ..media }.with_tags(tags))
+        }
+        Err(e) => Err(Error::new(e).context("Failed to execute ffprobe command")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ffprobe() {
+        let output = r#"{
+            "streams": [
+                { "index": 0, "codec_type": "video", "codec_name": "h264" },
+                { "index": 1, "codec_type": "audio", "codec_name": "aac", "sample_rate": "48000", "channels": 2, "tags": { "language": "eng", "handler_name": "Mic" } },
+                { "index": 2, "codec_type": "audio", "codec_name": "opus", "sample_rate": "48000", "channels": 1 }
+            ],
+            "format": {
+                "format_name": "matroska,webm",
+                "duration": "3605.120000",
+                "tags": { "TITLE": "Weekly sync", "creation_time": "2026-03-02T09:00:00.000000Z" }
+            }
+        }"#;
+
+        let media = parse_ffprobe(output.as_bytes(), 1024).unwrap();
+
+        assert_eq!(media.container.as_deref(), Some("matroska,webm"));
+        assert_eq!(media.duration, Some(3605.12));
+        assert_eq!(media.codecs, vec!["h264", "aac", "opus"]);
+        assert_eq!(media.audio_streams.len(), 2);
+        assert_eq!(media.audio_streams[0].index, 1);
+        assert_eq!(media.audio_streams[0].sample_rate, Some(48000));
+        assert_eq!(media.audio_streams[0].language.as_deref(), Some("eng"));
+        assert_eq!(media.title.as_deref(), Some("Weekly sync"));
+        assert_eq!(
+            media.recorded_at.as_deref(),
+            Some("2026-03-02T09:00:00.000000Z")
+        );
+    }
+}
@@ -1,4 +1,10 @@
 pub mod audio;
 pub mod commands;
+pub mod decoder;
+pub mod diarization;
 pub mod entities;
 pub mod language;
+pub mod media;
+pub mod pipeline;
+pub mod summarizer;
+pub mod vad;
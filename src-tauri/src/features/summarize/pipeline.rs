@@ -0,0 +1,1040 @@
+use std::{
+    future::Future,
+    io::SeekFrom,
+    ops::{Bound, RangeBounds},
+    path::PathBuf,
+    sync::Arc,
+    time::Instant,
+};
+
+use anyhow::{bail, ensure, Context, Error, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager, Wry};
+use tauri_plugin_store::Store;
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufWriter},
+    select,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    features::{
+        glossary::correction::{correct_segments, get_job_terms, initial_prompt},
+        job::entities::{Job, JobStage},
+        model::{
+            speech_to_text::{
+                Segment, SpeechToTextModel, TranscriptionMode, TranscriptionProgress,
+                TranscriptionSettings, Whisper, Word,
+            },
+            text_generation::{get_text_generation, Message, Role, TextGeneration},
+        },
+        speaker::{
+            entities::DiarizedSpeaker,
+            recognition::{recognize_speakers, save_speakers},
+        },
+        summarize::{
+            audio::{
+                stream_f32le_audio, AudioDecoder, AudioStream, DecodeRange, SampleReader,
+                SAMPLE_RATE,
+            },
+            diarization::{cluster_speakers, copy_speakers, segment_voices, VoiceEmbedding},
+            entities::{SourceFile, SourceFiles, Summary, SummaryTranscript},
+            language::Language,
+            media::probe_media,
+            summarizer::{summarize_segments, SummarizeStage},
+            vad::{speech_windows, SpeechAudio, SpeechAudioBuilder, SpeechDetector, SpeechRegion},
+        },
+    },
+    state::AppState,
+    utils::tauri::get_settings_store,
+};
+
+#[derive(Clone, Serialize)]
+struct SummarizationProgress {
+    #[serde(rename = "jobId")]
+    pub job_id: Uuid,
+    pub message: String,
+    #[serde(rename = "currentStep")]
+    pub current_step: u8,
+    #[serde(rename = "totalSteps")]
+    pub total_steps: u8,
+    /// Progress within the current step, from 0 to 100, for steps that report it
+    #[serde(rename = "stepProgress", skip_serializing_if = "Option::is_none")]
+    pub step_progress: Option<u8>,
+    #[serde(rename = "etaSeconds", skip_serializing_if = "Option::is_none")]
+    pub eta_seconds: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<Summary>,
+}
+
+/// Audio of one transcribed track, decoded into the job cache.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DecodedTrack {
+    /// Name the track's segments are labelled with, when tracks are transcribed separately
+    label: Option<String>,
+    audio_path: String,
+}
+
+/// Speech transcribed at once. Whisper only ever sees one window, so memory use stays the same
+/// however long the recording is.
+const TRANSCRIPTION_WINDOW_SAMPLES: usize = 5 * 60 * SAMPLE_RATE;
+
+/// Returned when a job stops because its cancellation token fired.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Job was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+async fn cancellable<T>(
+    cancellation_token: &CancellationToken,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    select! {
+        _ = cancellation_token.cancelled() => Err(Error::new(Cancelled)),
+        result = future => result,
+    }
+}
+
+pub async fn save_job_progress(database: &SqlitePool, job: &Job) -> Result<()> {
+    sqlx::query(
+        "UPDATE jobs SET status = ?, stage = ?, language = ?, language_confidence = ?, audio_path = ?, audio_tracks = ?, sources = ?, transcript = ?, original_transcript = ?, speakers = ?, speech_to_text_model = ?, transcription_settings = ?, media_info = ?, summary = ?, summary_id = ?, error = ? WHERE id = ?",
+    )
+    .bind(job.status)
+    .bind(job.stage)
+    .bind(job.language)
+    .bind(job.language_confidence)
+    .bind(&job.audio_path)
+    .bind(&job.audio_tracks)
+    .bind(&job.sources)
+    .bind(&job.transcript)
+    .bind(&job.original_transcript)
+    .bind(&job.speakers)
+    .bind(job.speech_to_text_model)
+    .bind(&job.transcription_settings)
+    .bind(&job.media_info)
+    .bind(&job.summary)
+    .bind(job.summary_id)
+    .bind(&job.error)
+    .bind(job.id)
+    .execute(database)
+    .await
+    .context("Failed to save job progress")?;
+
+    Ok(())
+}
+
+/// Reports how much of the recording is decoded, measured against its probed duration.
+fn decoding_progress(
+    app: &AppHandle,
+    job_id: Uuid,
+    duration: Option<f64>,
+) -> impl Fn(&'static str, usize) {
+    let app = app.clone();
+    let started_at = Instant::now();
+    let total_samples = duration
+        .map(|duration| duration * SAMPLE_RATE as f64)
+        .filter(|total| *total > 0.0);
+    move |message, decoded| {
+        let Some(total_samples) = total_samples else {
+            return;
+        };
+        let percent = (decoded as f64 / total_samples * 100.0).clamp(0.0, 100.0) as u8;
+        let eta = (percent > 0).then(|| {
+            started_at
+                .elapsed()
+                .mul_f64(f64::from(100 - percent) / f64::from(percent))
+        });
+
+        let result = app.emit(
+            "summarization_progress",
+            SummarizationProgress {
+                job_id,
+                message: message.to_string(),
+                current_step: 1,
+                total_steps: 4,
+                step_progress: Some(percent),
+                eta_seconds: eta.map(|eta| eta.as_secs()),
+                summary: None,
+            },
+        );
+        if let Err(e) = result {
+            error!("Failed to emit decoding progress event: {}", e);
+        }
+    }
+}
+
+/// Forwards whisper progress as summarization progress events of the transcription step.
+/// Whisper reports on one window at a time, which starts at `done` and covers `share` of the
+/// track, both as a fraction of the track's speech.
+fn transcription_progress(
+    app: &AppHandle,
+    job_id: Uuid,
+    message: &'static str,
+    done: f64,
+    share: f64,
+) -> impl Fn(TranscriptionProgress) + Send + 'static {
+    let app = app.clone();
+    move |progress| {
+        let window = f64::from(progress.percent) / 100.0;
+        let left = 1.0 - done - share * window;
+        // The rest of the track is assumed to go as fast as the rest of this window
+        let window_left = share * (1.0 - window);
+        let eta = progress
+            .eta
+            .filter(|_| window_left > 0.0)
+            .map(|eta| (eta.as_secs_f64() * left / window_left) as u64);
+
+        let result = app.emit(
+            "summarization_progress",
+            SummarizationProgress {
+                job_id,
+                message: message.to_string(),
+                current_step: 2,
+                total_steps: 4,
+                step_progress: Some(((1.0 - left) * 100.0).round().clamp(0.0, 100.0) as u8),
+                eta_seconds: eta,
+                summary: None,
+            },
+        );
+        if let Err(e) = result {
+            error!("Failed to emit transcription progress event: {}", e);
+        }
+    }
+}
+
+fn context_window(store: &Store<Wry>, text_generation: &dyn TextGeneration) -> Result<usize> {
+    let context_window = store
+        .get("model.textGeneration.contextWindow")
+        .map(serde_json::from_value::<usize>)
+        .transpose()
+        .context("Failed to parse text generation context window from settings")?
+        .unwrap_or_else(|| text_generation.context_window());
+
+    Ok(context_window)
+}
+
+/// Appends the samples of a stream to the audio cache as they are decoded, so the whole
+/// recording is never held in memory. Returns the number of samples written.
+async fn cache_stream(
+    stream: &mut AudioStream,
+    file: &mut BufWriter<File>,
+    cancellation_token: &CancellationToken,
+    mut on_chunk: impl FnMut(usize),
+) -> Result<usize> {
+    let mut written = 0;
+    while let Some(chunk) = cancellable(cancellation_token, stream.next_chunk())
+        .await
+        .context("Failed to decode audio")?
+    {
+        let bytes = chunk
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect::<Vec<u8>>();
+        file.write_all(&bytes)
+            .await
+            .context("Failed to cache decoded audio")?;
+        written += chunk.len();
+        on_chunk(chunk.len());
+    }
+
+    Ok(written)
+}
+
+/// Reads `range` of the decoded audio cached by the first step, one chunk at a time.
+async fn read_cached_audio(
+    audio_path: &str,
+    range: impl RangeBounds<usize>,
+    cancellation_token: &CancellationToken,
+    mut on_chunk: impl FnMut(&[f32]),
+) -> Result<()> {
+    let start = match range.start_bound() {
+        Bound::Included(start) => *start,
+        Bound::Excluded(start) => start + 1,
+        Bound::Unbounded => 0,
+    };
+    let length = match range.end_bound() {
+        Bound::Included(end) => (end + 1 - start) as u64,
+        Bound::Excluded(end) => (end - start) as u64,
+        Bound::Unbounded => u64::MAX / 4,
+    };
+
+    let mut file = File::open(audio_path)
+        .await
+        .context("Failed to open cached decoded audio")?;
+    file.seek(SeekFrom::Start(start as u64 * 4))
+        .await
+        .context("Failed to seek in cached decoded audio")?;
+    let mut reader = SampleReader::new(file.take(length * 4));
+    while let Some(chunk) = cancellable(cancellation_token, reader.next_chunk())
+        .await
+        .context("Failed to read cached decoded audio")?
+    {
+        on_chunk(&chunk);
+    }
+
+    Ok(())
+}
+
+/// Speech regions of a cached track, or the whole track when voice activity detection is off.
+async fn detect_speech_regions(
+    audio_path: &str,
+    vad_enabled: bool,
+    cancellation_token: &CancellationToken,
+) -> Result<Vec<SpeechRegion>> {
+    if vad_enabled {
+        let mut detector = SpeechDetector::default();
+        read_cached_audio(audio_path, .., cancellation_token, |chunk| {
+            detector.push(chunk)
+        })
+        .await?;
+        return Ok(detector.finish());
+    }
+
+    let length = tokio::fs::metadata(audio_path)
+        .await
+        .context("Failed to read cached decoded audio")?
+        .len() as usize
+        / 4;
+    Ok(vec![SpeechRegion {
+        start: 0,
+        end: length,
+    }])
+}
+
+/// Loads the speech of one window of a cached track. The returned audio maps its times to
+/// the start of the window, which is returned along with it in samples.
+async fn load_speech_window(
+    audio_path: &str,
+    window: &[SpeechRegion],
+    cancellation_token: &CancellationToken,
+) -> Result<(SpeechAudio, usize)> {
+    let start = window.first().map_or(0, |region| region.start);
+    let end = window.last().map_or(0, |region| region.end);
+    let regions = window
+        .iter()
+        .map(|region| SpeechRegion {
+            start: region.start - start,
+            end: region.end - start,
+        })
+        .collect();
+
+    let mut builder = SpeechAudioBuilder::new(regions);
+    read_cached_audio(audio_path, start..end, cancellation_token, |chunk| {
+        builder.push(chunk)
+    })
+    .await?;
+
+    Ok((builder.finish(), start))
+}
+
+/// Maps segments transcribed on the speech-only audio back to the recording's timeline.
+/// `offset` is where the cached audio starts on that timeline, when only a range was decoded.
+fn to_original_timeline(speech: &SpeechAudio, offset: f64, segments: Vec<Segment>) -> Vec<Segment> {
+    let to_original_time = |seconds: f64| offset + speech.to_original_time(seconds);
+    segments
+        .into_iter()
+        .map(|segment| Segment {
+            start: to_original_time(segment.start),
+            end: to_original_time(segment.end),
+            words: segment
+                .words
+                .into_iter()
+                .map(|word| Word {
+                    start: to_original_time(word.start),
+                    end: to_original_time(word.end),
+                    ..word
+                })
+                .collect(),
+            ..segment
+        })
+        .collect()
+}
+
+/// Voices of the segments of one window, see [`segment_voices`].
+async fn window_voices(
+    samples: Arc<Vec<f32>>,
+    segments: Vec<Segment>,
+) -> Result<(Vec<Segment>, Vec<Option<VoiceEmbedding>>)> {
+    tokio::task::spawn_blocking(move || {
+        let voices = segment_voices(&samples, &segments);
+        (segments, voices)
+    })
+    .await
+    .context("Speaker diarization thread panicked")
+}
+
+/// Labels the segments of a track with the speakers found among the voices of all its windows.
+async fn diarize(
+    voices: Vec<Option<VoiceEmbedding>>,
+    mut segments: Vec<Segment>,
+    cancellation_token: &CancellationToken,
+) -> Result<(Vec<Segment>, Vec<VoiceEmbedding>)> {
+    let cancellation_token = cancellation_token.clone();
+    tokio::task::spawn_blocking(move || {
+        let voices = cluster_speakers(voices, &mut segments, &cancellation_token)?;
+        Ok((segments, voices))
+    })
+    .await
+    .context("Speaker diarization thread panicked")?
+}
+
+/// Runs every stage of a summarization job that has not completed yet. The output of each
+/// stage is persisted on the job, so a job interrupted by an app restart resumes from there.
+pub async fn run_summarize_job(
+    app: &AppHandle,
+    database: &SqlitePool,
+    job: &mut Job,
+    cancellation_token: &CancellationToken,
+) -> Result<Summary> {
+    let job_id = job.id;
+    let emit_progress = |message: &str, step: u8, summary: Option<Summary>| {
+        app.emit(
+            "summarization_progress",
+            SummarizationProgress {
+                job_id,
+                message: message.to_string(),
+                current_step: step,
+                total_steps: 4,
+                step_progress: None,
+                eta_seconds: None,
+                summary,
+            },
+        )
+        .context("Failed to emit summarization progress event")
+    };
+
+    let store = get_settings_store(app).context("Failed to get settings store")?;
+    let cache_dir = app
+        .path()
+        .app_cache_dir()
+        .context("Could not determine app cache directory")?
+        .join("jobs");
+
+    // Step 1: Load audio
+    if job.stage < JobStage::AudioDecoded {
+        emit_progress("Loading audio...", 1, None)?;
+        let mut sources = match job.sources.clone() {
+            Some(sources) => sources.0,
+            None => vec![SourceFile {
+                path: job.file_path.clone(),
+                offset: None,
+                duration: None,
+            }],
+        };
+        let selection = job.audio_selection.clone().unwrap_or_default();
+        let mut media = Vec::with_capacity(sources.len());
+        for source in sources.iter_mut() {
+            let info = cancellable(
+                cancellation_token,
+                probe_media(&PathBuf::from(&source.path)),
+            )
+            .await
+            .with_context(|| format!("Failed to read media metadata of {}", source.path))?;
+            selection
+                .validate(&info)
+                .with_context(|| format!("Invalid audio selection for {}", source.path))?;
+            source.duration = info.duration;
+            media.push(info);
+        }
+
+        let start_time = job.start_time.unwrap_or(0.0);
+        let end_time = job.end_time;
+        let total_duration = sources
+            .iter()
+            .map(|source| source.duration)
+            .sum::<Option<f64>>();
+        // Where a range starts in a later file depends on the length of the files before it
+        ensure!(
+            sources.len() == 1
+                || (start_time == 0.0 && end_time.is_none())
+                || total_duration.is_some(),
+            "The duration of every file must be known to transcribe a range of several files"
+        );
+        let selected_tracks = selection.tracks(&media[0]);
+        job.media_info = media.into_iter().next();
+
+        tokio::fs::create_dir_all(&cache_dir)
+            .await
+            .context("Failed to create job cache directory")?;
+        let on_progress = decoding_progress(
+            app,
+            job_id,
+            total_duration.map(|total| {
+                let length = end_time.unwrap_or(total).min(total) - start_time;
+                length.max(0.0) * selected_tracks.len() as f64
+            }),
+        );
+        let mut decoded = 0;
+        let mut tracks = Vec::with_capacity(selected_tracks.len());
+        for (index, (label, selection)) in selected_tracks.into_iter().enumerate() {
+            let audio_path = match label {
+                Some(_) => cache_dir.join(format!("{}.{}.f32", job_id, index)),
+                None => cache_dir.join(format!("{}.f32", job_id)),
+            };
+            let mut file = BufWriter::new(
+                File::create(&audio_path)
+                    .await
+                    .context("Failed to create decoded audio cache")?,
+            );
+
+            // Files are decoded one after another into the same cache, so the cached audio
+            // is the requested range of their combined timeline
+            let mut offset = 0.0;
+            for source in sources.iter_mut() {
+                source.offset = Some(offset);
+                let range = DecodeRange {
+                    start: (start_time - offset).max(0.0),
+                    end: end_time.map(|end| end - offset),
+                };
+                let ends_before = range.end.is_some_and(|end| end <= 0.0);
+                let starts_after = source
+                    .duration
+                    .is_some_and(|duration| range.start >= duration);
+
+                let mut written = 0;
+                if !ends_before && !starts_after {
+                    let mut stream = cancellable(
+                        cancellation_token,
+                        stream_f32le_audio(&PathBuf::from(&source.path), &selection, range),
+                    )
+                    .await
+                    .context("Failed to load audio data")?;
+                    let decoder = stream.decoder();
+                    info!(id=%job_id, decoder=%decoder, path=%source.path, track=?label, "Decoding audio");
+                    let message = match decoder {
+                        AudioDecoder::Ffmpeg => "Loading audio...",
+                        AudioDecoder::Native => "Loading audio with the built-in decoder...",
+                    };
+
+                    written = cache_stream(&mut stream, &mut file, cancellation_token, |samples| {
+                        decoded += samples;
+                        on_progress(message, decoded);
+                    })
+                    .await?;
+                }
+                offset += source
+                    .duration
+                    .unwrap_or(written as f64 / SAMPLE_RATE as f64);
+            }
+            file.flush()
+                .await
+                .context("Failed to cache decoded audio")?;
+
+            tracks.push(DecodedTrack {
+                label,
+                audio_path: audio_path.to_string_lossy().to_string(),
+            });
+        }
+
+        if tracks.len() > 1 {
+            job.audio_tracks =
+                Some(serde_json::to_string(&tracks).context("Failed to serialize audio tracks")?);
+        } else {
+            job.audio_path = tracks.pop().map(|track| track.audio_path);
+        }
+        job.sources = Some(SourceFiles(sources));
+        job.stage = JobStage::AudioDecoded;
+        save_job_progress(database, job).await?;
+    }
+
+    // Step 2: Transcribe audio
+    let segments = if job.stage < JobStage::Transcribed {
+        let tracks = match job.audio_tracks.as_deref() {
+            Some(tracks) => serde_json::from_str::<Vec<DecodedTrack>>(tracks)
+                .context("Failed to parse decoded audio tracks")?,
+            None => vec![DecodedTrack {
+                label: None,
+                audio_path: job
+                    .audio_path
+                    .clone()
+                    .context("Decoded audio is missing from the job")?,
+            }],
+        };
+
+        let stt_model: SpeechToTextModel = serde_json::from_value(
+            store
+                .get("model.speechToText")
+                .context("Failed to get speechToText model from settings store")?,
+        )
+        .context("Failed to parse speech-to-text model from settings")?;
+        let settings =
+            TranscriptionSettings::from_store(&store).context("Invalid transcription settings")?;
+
+        let message = match job.transcription_mode {
+            TranscriptionMode::Transcribe => "Transcribing audio...",
+            TranscriptionMode::Translate => "Translating audio...",
+        };
+        emit_progress(message, 2, None)?;
+        let whisper_context = app
+            .state::<AppState>()
+            .whisper_cache
+            .get(stt_model, &settings)
+            .await
+            .context("Failed to load speech-to-text model")?;
+        job.speech_to_text_model = Some(stt_model);
+        job.transcription_settings = Some(settings.clone());
+        let speech_to_text = Whisper::new(whisper_context, settings);
+
+        // Only speech is transcribed, whisper tends to hallucinate text in long silences
+        let vad_enabled = store
+            .get("transcription.vad")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(true);
+        // Separately recorded tracks already tell the speakers apart
+        let diarization_enabled = tracks.len() == 1
+            && store
+                .get("transcription.diarization")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(false);
+        let keep_original =
+            job.transcription_mode == TranscriptionMode::Translate && job.keep_original_transcript;
+
+        let timeline_offset = job.start_time.unwrap_or(0.0);
+
+        let glossary = get_job_terms(database, job_id).await?;
+        let initial_prompt = initial_prompt(&glossary);
+
+        let mut segments = Vec::new();
+        let mut original_segments = Vec::new();
+        let mut speakers = Vec::new();
+        let mut has_speech = false;
+        for track in &tracks {
+            let regions =
+                detect_speech_regions(&track.audio_path, vad_enabled, cancellation_token).await?;
+            let windows = speech_windows(&regions, TRANSCRIPTION_WINDOW_SAMPLES);
+            if windows.is_empty() {
+                info!(id=%job_id, track=?track.label, "No speech detected in audio track");
+                continue;
+            }
+            has_speech = true;
+
+            let track_length = regions
+                .iter()
+                .map(|region| region.end - region.start)
+                .sum::<usize>() as f64;
+            let mut done = 0.0;
+            let mut track_segments = Vec::new();
+            let mut track_original_segments = Vec::new();
+            let mut voices = Vec::new();
+            // Windows are loaded one at a time, so a long recording never sits in memory whole
+            for window in &windows {
+                let (mut speech, window_start) =
+                    load_speech_window(&track.audio_path, window, cancellation_token).await?;
+                let window_offset = timeline_offset + window_start as f64 / SAMPLE_RATE as f64;
+                // With the original transcript kept, every window is transcribed twice
+                let share = window
+                    .iter()
+                    .map(|region| region.end - region.start)
+                    .sum::<usize>() as f64
+                    / track_length
+                    / if keep_original { 2.0 } else { 1.0 };
+
+                if matches!(job.language, Language::Auto) {
+                    emit_progress("Detecting language...", 2, None)?;
+                    let (language, confidence) = speech_to_text
+                        .detect_language(&speech.samples)
+                        .await
+                        .context("Failed to detect spoken language")?;
+                    info!(id=%job_id, language=%language, confidence, "Detected spoken language");
+
+                    job.language = language;
+                    job.language_confidence = Some(f64::from(confidence));
+                    emit_progress(message, 2, None)?;
+                }
+
+                // Transcription, diarization and the original-language pass share one buffer
+                let samples = Arc::new(std::mem::take(&mut speech.samples));
+
+                // Not wrapped in `cancellable`: the job waits for whisper to abort, so the next
+                // job does not start while the previous one is still holding the model in memory
+                let mut window_segments = speech_to_text
+                    .transcribe(
+                        Arc::clone(&samples),
+                        job.language,
+                        job.transcription_mode,
+                        initial_prompt.clone(),
+                        cancellation_token.clone(),
+                        transcription_progress(app, job_id, message, done, share),
+                    )
+                    .await
+                    .context("Failed to transcript audio")?;
+                done += share;
+                correct_segments(&mut window_segments, &glossary);
+
+                // Voices are taken from the speech-only audio the segments were transcribed on
+                if diarization_enabled {
+                    let (described, described_voices) =
+                        window_voices(Arc::clone(&samples), window_segments).await?;
+                    window_segments = described;
+                    voices.extend(described_voices);
+                }
+                track_segments.extend(to_original_timeline(
+                    &speech,
+                    window_offset,
+                    window_segments,
+                ));
+
+                if keep_original {
+                    let message = "Transcribing original audio...";
+                    let mut original = speech_to_text
+                        .transcribe(
+                            samples,
+                            job.language,
+                            TranscriptionMode::Transcribe,
+                            initial_prompt.clone(),
+                            cancellation_token.clone(),
+                            transcription_progress(app, job_id, message, done, share),
+                        )
+                        .await
+                        .context("Failed to transcript audio in its original language")?;
+                    correct_segments(&mut original, &glossary);
+                    track_original_segments.extend(to_original_timeline(
+                        &speech,
+                        window_offset,
+                        original,
+                    ));
+                    done += share;
+                }
+            }
+
+            // Speakers are clustered over the whole track, so labels agree across windows
+            if diarization_enabled {
+                emit_progress("Identifying speakers...", 2, None)?;
+                let (mut diarized, voices) =
+                    diarize(voices, track_segments, cancellation_token).await?;
+                speakers = recognize_speakers(database, &mut diarized, voices)
+                    .await
+                    .context("Failed to recognize speakers")?;
+                track_segments = diarized;
+                copy_speakers(&track_segments, &mut track_original_segments);
+            }
+
+            if let Some(label) = &track.label {
+                for segment in track_segments
+                    .iter_mut()
+                    .chain(track_original_segments.iter_mut())
+                {
+                    segment.speaker = Some(label.clone());
+                }
+                speakers.push(DiarizedSpeaker {
+                    name: label.clone(),
+                    profile_id: None,
+                    embedding: None,
+                });
+            }
+            segments.extend(track_segments);
+            original_segments.extend(track_original_segments);
+        }
+        if !has_speech {
+            bail!("No speech was detected in the recording");
+        }
+
+        // Tracks run in parallel, so their segments are interleaved into one timeline
+        segments.sort_by(|a, b| a.start.total_cmp(&b.start));
+        original_segments.sort_by(|a, b| a.start.total_cmp(&b.start));
+        if !speakers.is_empty() {
+            job.speakers =
+                Some(serde_json::to_string(&speakers).context("Failed to serialize speakers")?);
+        }
+        if keep_original {
+            job.original_transcript = Some(
+                serde_json::to_string(&original_segments)
+                    .context("Failed to serialize original transcript")?,
+            );
+        }
+
+        for track in tracks {
+            let _ = tokio::fs::remove_file(track.audio_path).await;
+        }
+        job.audio_path = None;
+        job.audio_tracks = None;
+        job.transcript =
+            Some(serde_json::to_string(&segments).context("Failed to serialize transcript")?);
+        job.stage = JobStage::Transcribed;
+        save_job_progress(database, job).await?;
+
+        segments
+    } else {
+        serde_json::from_str::<Vec<Segment>>(
+            job.transcript
+                .as_deref()
+                .context("Transcript is missing from the job")?,
+        )
+        .context("Failed to parse saved transcript")?
+    };
+
+    // Step 3: Generate summary and title
+    let text_generation = get_text_generation(app)
+        .await
+        .context("Failed to initialize text generation model")?;
+
+    // A translated transcript is in English, so the summary is written in English too
+    let summary_language = match job.transcription_mode {
+        TranscriptionMode::Transcribe => job.language,
+        TranscriptionMode::Translate => Language::English,
+    };
+
+    if job.stage < JobStage::Summarized {
+        let context_window = context_window(&store, text_generation.as_ref())?;
+        let summarize_result = cancellable(
+            cancellation_token,
+            summarize_segments(
+                text_generation.as_ref(),
+                &segments,
+                summary_language,
+                context_window,
+                job.template.as_deref(),
+                |stage| match stage {
+                    SummarizeStage::Summarizing => emit_progress("Generating summary...", 3, None),
+                    SummarizeStage::SummarizingChunk { current, total } => emit_progress(
+                        &format!("Summarizing part {} of {}...", current, total),
+                        3,
+                        None,
+                    ),
+                    SummarizeStage::Condensing => {
+                        emit_progress("Shortening partial summaries...", 3, None)
+                    }
+                    SummarizeStage::Merging => {
+                        emit_progress("Merging partial summaries...", 3, None)
+                    }
+                },
+            ),
+        )
+        .await?;
+
+        job.summary = Some(summarize_result);
+        job.stage = JobStage::Summarized;
+        save_job_progress(database, job).await?;
+    }
+
+    let summarize_result = job
+        .summary
+        .clone()
+        .context("Summary is missing from the job")?;
+    let summary_title = cancellable(cancellation_token, async {
+        text_generation
+            .generate_text(vec![
+                Message {
+                    role: Role::System,
+                    text: "
+                    You will receive a summarized personal note written in Markdown.
+
+                    Assume this summary represents the main content of a personal voice note.
+                    Your task is to generate a **short, clear, and descriptive title** based only on the summary content.
+
+                    Title requirements:
+                    - One line only
+                    - No punctuation at the end
+                    - No quotation marks
+                    - No emojis
+                    - Do not add information not present in the summary
+                    - Keep it concise and neutral
+
+                    Write the title in the same language as the summary.
+                    "
+                    .to_string(),
+                },
+                Message {
+                    role: Role::User,
+                    text: summarize_result.clone(),
+                },
+            ])
+            .await
+            .context("Failed to generate summary title")
+    })
+    .await?
+    .into_iter()
+    .last()
+    .map(|m| m.text)
+    .unwrap_or_else(|| "Untitled Summary".to_string());
+
+    // Save to database
+    let mut tx = database
+        .begin()
+        .await
+        .context("Failed to begin database transaction")?;
+    let summary_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO summaries (id, title, language, language_confidence, transcription_mode, speech_to_text_model, transcription_settings, media_info, audio_selection, sources, start_time, end_time, summary, file_path) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&summary_id)
+    .bind(&summary_title)
+    .bind(job.language.code())
+    .bind(job.language_confidence)
+    .bind(job.transcription_mode)
+    .bind(job.speech_to_text_model)
+    .bind(&job.transcription_settings)
+    .bind(&job.media_info)
+    .bind(&job.audio_selection)
+    .bind(&job.sources)
+    .bind(job.start_time)
+    .bind(job.end_time)
+    .bind(&summarize_result)
+    .bind(&job.file_path)
+    .execute(&mut *tx)
+    .await
+    .context("Failed to insert summary into database")?;
+
+    let original_segments = job
+        .original_transcript
+        .as_deref()
+        .map(serde_json::from_str::<Vec<Segment>>)
+        .transpose()
+        .context("Failed to parse saved original transcript")?
+        .unwrap_or_default();
+    let speakers = job
+        .speakers
+        .as_deref()
+        .map(serde_json::from_str::<Vec<DiarizedSpeaker>>)
+        .transpose()
+        .context("Failed to parse saved speakers")?
+        .unwrap_or_default();
+    let speaker_ids = save_speakers(&mut *tx, summary_id, &speakers).await?;
+
+    let transcripts = segments
+        .into_iter()
+        .map(|segment| (segment, false))
+        .chain(original_segments.into_iter().map(|segment| (segment, true)));
+    for (segment, is_original) in transcripts {
+        let transcript_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO summary_transcripts (id, summary_id, text, start_time, end_time, is_original, speaker, speaker_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&transcript_id)
+        .bind(&summary_id)
+        .bind(&segment.text)
+        .bind(segment.start)
+        .bind(segment.end)
+        .bind(is_original)
+        .bind(&segment.speaker)
+        .bind(segment.speaker.as_ref().and_then(|name| speaker_ids.get(name)))
+        .execute(&mut *tx)
+        .await
+        .context("Failed to insert summary transcript into database")?;
+
+        for (position, word) in segment.words.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO transcript_words (id, transcript_id, position, text, start_time, end_time, probability) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(Uuid::new_v4())
+            .bind(&transcript_id)
+            .bind(position as i64)
+            .bind(&word.text)
+            .bind(word.start)
+            .bind(word.end)
+            .bind(word.probability)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to insert transcript word into database")?;
+        }
+    }
+
+    tx.commit()
+        .await
+        .context("Failed to commit database transaction")?;
+
+    // Fetch the inserted summary
+    let summary = sqlx::query_as::<_, Summary>("SELECT * FROM summaries WHERE id = ?")
+        .bind(&summary_id)
+        .fetch_one(database)
+        .await
+        .context("Failed to fetch inserted summary")?;
+    job.summary_id = Some(summary_id);
+
+    // Step 4: Emit completion
+    emit_progress("Completed!", 4, Some(summary.clone()))?;
+
+    Ok(summary)
+}
+
+/// Writes the summary of an existing recording again from its stored transcript, so renamed
+/// or merged speakers show up in it. The title is kept. Returns `None` if the summary does not
+/// exist.
+pub async fn regenerate_summary(
+    app: &AppHandle,
+    database: &SqlitePool,
+    summary_id: Uuid,
+    cancellation_token: &CancellationToken,
+) -> Result<Option<Summary>> {
+    let Some(summary) = sqlx::query_as::<_, Summary>("SELECT * FROM summaries WHERE id = ?")
+        .bind(summary_id)
+        .fetch_optional(database)
+        .await
+        .context("Failed to fetch summary from database")?
+    else {
+        return Ok(None);
+    };
+
+    let segments = sqlx::query_as::<_, SummaryTranscript>(
+        "SELECT * FROM summary_transcripts WHERE summary_id = ? AND is_original = 0 ORDER BY start_time ASC",
+    )
+    .bind(summary_id)
+    .fetch_all(database)
+    .await
+    .context("Failed to fetch summary transcripts from database")?
+    .into_iter()
+    .map(|transcript| Segment {
+        text: transcript.text,
+        start: transcript.start_time,
+        end: transcript.end_time,
+        words: Vec::new(),
+        speaker: transcript.speaker,
+    })
+    .collect::<Vec<Segment>>();
+
+    let template =
+        sqlx::query_scalar::<_, Option<String>>("SELECT template FROM jobs WHERE summary_id = ?")
+            .bind(summary_id)
+            .fetch_optional(database)
+            .await
+            .context("Failed to fetch summary template from database")?
+            .flatten();
+
+    let language = match summary.transcription_mode {
+        TranscriptionMode::Transcribe => summary.language.parse().unwrap_or(Language::English),
+        TranscriptionMode::Translate => Language::English,
+    };
+
+    let store = get_settings_store(app).context("Failed to get settings store")?;
+    let text_generation = get_text_generation(app)
+        .await
+        .context("Failed to initialize text generation model")?;
+    let text = cancellable(
+        cancellation_token,
+        summarize_segments(
+            text_generation.as_ref(),
+            &segments,
+            language,
+            context_window(&store, text_generation.as_ref())?,
+            template.as_deref(),
+            |_| Ok(()),
+        ),
+    )
+    .await?;
+
+    sqlx::query("UPDATE summaries SET summary = ? WHERE id = ?")
+        .bind(&text)
+        .bind(summary_id)
+        .execute(database)
+        .await
+        .context("Failed to update summary")?;
+
+    let summary = sqlx::query_as::<_, Summary>("SELECT * FROM summaries WHERE id = ?")
+        .bind(summary_id)
+        .fetch_one(database)
+        .await
+        .context("Failed to fetch regenerated summary")?;
+
+    Ok(Some(summary))
+}
@@ -0,0 +1,575 @@
+use anyhow::{bail, Context, Result};
+
+use crate::features::{
+    model::{
+        speech_to_text::Segment,
+        text_generation::{Message, Role, TextGeneration},
+    },
+    summarize::language::Language,
+};
+
+/// Tokens kept free for the system prompt and the generated summary.
+const RESERVED_TOKENS: usize = 3072;
+/// Share of each chunk repeated at the start of the next one so no thought is cut in half.
+const OVERLAP_RATIO: usize = 10;
+
+/// Stage of a (possibly chunked) summarization, reported to the caller for progress updates.
+pub enum SummarizeStage {
+    Summarizing,
+    SummarizingChunk {
+        current: usize,
+        total: usize,
+    },
+    /// Partial summaries are too long to merge in pairs and are shortened first
+    Condensing,
+    Merging,
+}
+
+/// Rough token estimate without a tokenizer. ASCII text averages about four characters
+/// per token, while CJK and other scripts are closer to one token per character.
+pub fn estimate_tokens(text: &str) -> usize {
+    let (ascii, other) = text.chars().fold((0, 0), |(ascii, other), c| {
+        if c.is_ascii() {
+            (ascii + 1, other)
+        } else {
+            (ascii, other + 1)
+        }
+    });
+
+    ascii.div_ceil(4) + other
+}
+
+/// Splits segments into chunks of at most `max_tokens`, where each chunk starts with up to
+/// `overlap_tokens` worth of segments from the end of the previous chunk.
+/// A single segment larger than `max_tokens` still becomes its own chunk.
+pub fn chunk_segments(
+    segments: &[Segment],
+    max_tokens: usize,
+    overlap_tokens: usize,
+) -> Vec<Vec<Segment>> {
+    let mut chunks = Vec::new();
+    let mut current: Vec<Segment> = Vec::new();
+    let mut current_tokens = 0;
+
+    for segment in segments {
+        let tokens = estimate_tokens(&segment.text);
+
+        if !current.is_empty() && current_tokens + tokens > max_tokens {
+            let mut overlap = Vec::new();
+            let mut overlap_size = 0;
+            for previous in current.iter().rev() {
+                let previous_tokens = estimate_tokens(&previous.text);
+                if overlap_size + previous_tokens > overlap_tokens {
+                    break;
+                }
+                overlap_size += previous_tokens;
+                overlap.push(previous.clone());
+            }
+            overlap.reverse();
+
+            chunks.push(std::mem::replace(&mut current, overlap));
+            current_tokens = overlap_size;
+        }
+
+        current.push(segment.clone());
+        current_tokens += tokens;
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+/// Summarizes a transcript, splitting it into chunks when it does not fit in the context
+/// window. Each chunk is summarized on its own (map) and the partial summaries are then
+/// merged (reduce), repeatedly if the partial summaries are still too long.
+pub async fn summarize_segments(
+    text_generation: &dyn TextGeneration,
+    segments: &[Segment],
+    language: Language,
+    context_window: usize,
+    template: Option<&str>,
+    on_stage: impl Fn(SummarizeStage) -> Result<()>,
+) -> Result<String> {
+    let max_tokens = context_window.saturating_sub(RESERVED_TOKENS).max(512);
+    let chunks = chunk_segments(segments, max_tokens, max_tokens / OVERLAP_RATIO);
+    let has_speakers = segments.iter().any(|s| s.speaker.is_some());
+
+    if chunks.len() <= 1 {
+        on_stage(SummarizeStage::Summarizing)?;
+
+        return generate(
+            text_generation,
+            with_template(
+                with_speakers(get_summary_prompt(language), has_speakers),
+                template,
+            ),
+            segments_to_text(segments),
+        )
+        .await
+        .context("Failed to generate summary text");
+    }
+
+    let total = chunks.len();
+    let mut partial_summaries = Vec::with_capacity(total);
+    for (index, chunk) in chunks.iter().enumerate() {
+        on_stage(SummarizeStage::SummarizingChunk {
+            current: index + 1,
+            total,
+        })?;
+
+        let partial_summary = generate(
+            text_generation,
+            with_speakers(
+                get_chunk_summary_prompt(language, index + 1, total),
+                has_speakers,
+            ),
+            segments_to_text(chunk),
+        )
+        .await
+        .with_context(|| format!("Failed to summarize part {} of {}", index + 1, total))?;
+        partial_summaries.push(partial_summary);
+    }
+
+    loop {
+        let mut groups = group_summaries(&partial_summaries, max_tokens);
+        // No two partial summaries fit in one request, so merging would not shrink the list
+        if groups.len() > 1 && groups.len() == partial_summaries.len() {
+            on_stage(SummarizeStage::Condensing)?;
+
+            let mut condensed = Vec::with_capacity(partial_summaries.len());
+            for summary in &partial_summaries {
+                condensed.push(
+                    generate(
+                        text_generation,
+                        with_speakers(get_condense_prompt(language), has_speakers),
+                        summaries_to_text(std::slice::from_ref(summary)),
+                    )
+                    .await
+                    .context("Failed to shorten partial summary")?,
+                );
+            }
+
+            partial_summaries = condensed;
+            groups = group_summaries(&partial_summaries, max_tokens);
+            if groups.len() == partial_summaries.len() {
+                bail!(
+                    "Partial summaries are too long to merge within a context window of {} tokens",
+                    context_window
+                );
+            }
+        }
+
+        on_stage(SummarizeStage::Merging)?;
+        if groups.len() == 1 {
+            return generate(
+                text_generation,
+                with_template(
+                    with_speakers(get_merge_prompt(language), has_speakers),
+                    template,
+                ),
+                summaries_to_text(&partial_summaries),
+            )
+            .await
+            .context("Failed to merge partial summaries");
+        }
+
+        let mut merged = Vec::with_capacity(groups.len());
+        for group in &groups {
+            merged.push(
+                generate(
+                    text_generation,
+                    with_speakers(get_merge_prompt(language), has_speakers),
+                    summaries_to_text(group),
+                )
+                .await
+                .context("Failed to merge partial summaries")?,
+            );
+        }
+
+        partial_summaries = merged;
+    }
+}
+
+fn group_summaries(summaries: &[String], max_tokens: usize) -> Vec<Vec<String>> {
+    let mut groups: Vec<Vec<String>> = Vec::new();
+    let mut current_tokens = 0;
+
+    for summary in summaries {
+        let tokens = estimate_tokens(summary);
+        match groups.last_mut() {
+            Some(group) if current_tokens + tokens <= max_tokens => {
+                group.push(summary.clone());
+                current_tokens += tokens;
+            }
+            _ => {
+                groups.push(vec![summary.clone()]);
+                current_tokens = tokens;
+            }
+        }
+    }
+
+    groups
+}
+
+async fn generate(
+    text_generation: &dyn TextGeneration,
+    system_prompt: String,
+    user_text: String,
+) -> Result<String> {
+    let text = text_generation
+        .generate_text(vec![
+            Message {
+                role: Role::System,
+                text: system_prompt,
+            },
+            Message {
+                role: Role::User,
+                text: user_text,
+            },
+        ])
+        .await?
+        .into_iter()
+        .last()
+        .map(|m| m.text)
+        .unwrap_or_default();
+
+    Ok(text)
+}
+
+/// Appends the user's note template to a prompt that produces the final summary.
+fn with_template(prompt: String, template: Option<&str>) -> String {
+    match template.map(str::trim).filter(|t| !t.is_empty()) {
+        Some(template) => format!(
+            "{}
+        Structure the summary using the following template. Leave out sections that the
+        transcription has no information for.
+
+        <template>
+        {}
+        </template>
+        ",
+            prompt, template
+        ),
+        None => prompt,
+    }
+}
+
+/// Asks the model to keep track of who said what when the transcript is diarized.
+fn with_speakers(prompt: String, has_speakers: bool) -> String {
+    if !has_speakers {
+        return prompt;
+    }
+
+    format!(
+        "{}
+        The recording has multiple speakers, labelled \"Speaker 1\", \"Speaker 2\", and so on.
+        In the transcription each line starts with the label of the person speaking.
+        Attribute ideas, decisions, and action items to the speaker who made or committed
+        to them, using the same labels.
+        ",
+        prompt
+    )
+}
+
+fn get_summary_prompt(language: Language) -> String {
+    format!(
+        "
+        You will receive an audio transcription generated by Whisper.
+        The transcription consists of multiple segments with the text attribute.
+        Ignore the timing information and focus only on the textual content.
+
+        *Transcription language:* {}
+
+        Assume this transcription is a *personal voice note recorded by the user*.
+        Your task is to transform the raw transcription into a *concise note that is easy to read and review later.*
+
+        *When summarizing:*
+        - Combine all segments into a coherent understanding
+        - Remove repetitions, filler words, and unimportant fragments
+        - Preserve the main ideas, thoughts, or information worth
+
+        *Output requirements*:
+        - Use Markdown format
+        - Use short paragraphs or bullet points when appropriate
+        - Do not include timestamps or segment references
+
+        Write the summary in {} using clear, natural, and concise wording.
+        Do not add any information that is not present in the transcription.
+        ",
+        language.to_display_name(),
+        language.to_display_name()
+    )
+}
+
+fn get_chunk_summary_prompt(language: Language, part: usize, total_parts: usize) -> String {
+    format!(
+        "
+        You will receive part {} of {} of an audio transcription generated by Whisper.
+        The recording was too long to process at once, so it was split into consecutive parts.
+        The beginning of this part may repeat the end of the previous part.
+
+        *Transcription language:* {}
+
+        Assume this transcription is part of a *personal voice note recorded by the user*.
+        Your task is to write *detailed notes* about this part only. They will later be merged
+        with the notes of the other parts into one final summary.
+
+        *When writing notes:*
+        - Keep every idea, decision, action item, name, and number that appears in this part
+        - Remove repetitions, filler words, and unimportant fragments
+        - Do not write an introduction or a conclusion for the whole recording
+
+        *Output requirements*:
+        - Use Markdown bullet points
+        - Do not include timestamps or segment references
+
+        Write the notes in {}.
+        Do not add any information that is not present in the transcription.
+        ",
+        part,
+        total_parts,
+        language.to_display_name(),
+        language.to_display_name()
+    )
+}
+
+fn get_merge_prompt(language: Language) -> String {
+    format!(
+        "
+        You will receive notes written about consecutive parts of one long personal voice note.
+        Each part is wrapped in a <part> tag, in the order they were recorded.
+        Neighbouring parts may overlap and mention the same things.
+
+        Your task is to merge them into a *single concise note that is easy to read and review later.*
+
+        *When merging:*
+        - Combine the parts into a coherent understanding of the whole recording
+        - Remove duplicates caused by overlapping parts
+        - Preserve the main ideas, thoughts, or information worth
+
+        *Output requirements*:
+        - Use Markdown format
+        - Use short paragraphs or bullet points when appropriate
+        - Do not mention the parts or how the note was split
+
+        Write the summary in {} using clear, natural, and concise wording.
+        Do not add any information that is not present in the notes.
+        ",
+        language.to_display_name()
+    )
+}
+
+fn get_condense_prompt(language: Language) -> String {
+    format!(
+        "
+        You will receive notes about one part of a long personal voice note, wrapped in a
+        <part> tag. They are too long to be merged with the notes of the other parts.
+
+        Your task is to shorten them to *at most half their length.*
+
+        *When shortening:*
+        - Keep the decisions, action items, names, and numbers
+        - Drop details that do not change the meaning
+        - Do not write an introduction or a conclusion
+
+        *Output requirements*:
+        - Use Markdown bullet points
+        - Do not mention the part or that the notes were shortened
+
+        Write the notes in {}.
+        Do not add any information that is not present in the notes.
+        ",
+        language.to_display_name()
+    )
+}
+
+pub fn segments_to_text(segments: &[Segment]) -> String {
+    let content = segments
+        .iter()
+        .filter(|s| !s.text.trim().is_empty())
+        .map(|s| match &s.speaker {
+            Some(speaker) => format!("{}: {}", speaker, s.text.trim()),
+            None => s.text.trim().to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!("<transcription>\n{}\n</transcription>", content)
+}
+
+fn summaries_to_text(summaries: &[String]) -> String {
+    summaries
+        .iter()
+        .map(|s| format!("<part>\n{}\n</part>", s.trim()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::stream::BoxStream;
+    use tokio_util::sync::CancellationToken;
+
+    use super::*;
+    use crate::features::model::text_generation::{Model, Provider};
+
+    /// Answers with text of a fixed length per kind of prompt.
+    struct FakeTextGeneration {
+        chunk_tokens: usize,
+        condensed_tokens: usize,
+    }
+
+    #[async_trait::async_trait]
+    impl TextGeneration for FakeTextGeneration {
+        fn get_provider(&self) -> Provider {
+            Provider::LlamaCpp
+        }
+
+        async fn get_models(&self) -> Result<Vec<Model>> {
+            Ok(Vec::new())
+        }
+
+        async fn generate_text(&self, messages: Vec<Message>) -> Result<Vec<Message>> {
+            let system_prompt = &messages[0].text;
+            let text = if system_prompt.contains("at most half their length") {
+                "c".repeat(self.condensed_tokens * 4)
+            } else if system_prompt.contains("<part> tag") {
+                "merged".to_string()
+            } else {
+                "p".repeat(self.chunk_tokens * 4)
+            };
+
+            Ok(vec![Message {
+                role: Role::Assistant,
+                text,
+            }])
+        }
+
+        fn generate_text_stream(
+            &self,
+            _messages: Vec<Message>,
+            _cancellation_token: CancellationToken,
+        ) -> BoxStream<'_, Result<String>> {
+            unimplemented!()
+        }
+    }
+
+    /// Three chunks with a context window of 512 tokens left for the transcript.
+    fn long_transcript() -> Vec<Segment> {
+        ["a", "b", "c"]
+            .into_iter()
+            .map(|c| segment(&c.repeat(1200)))
+            .collect()
+    }
+
+    fn segment(text: &str) -> Segment {
+        Segment {
+            text: text.to_string(),
+            start: 0.0,
+            end: 0.0,
+            words: Vec::new(),
+            speaker: None,
+        }
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(estimate_tokens("日本語"), 3);
+    }
+
+    #[test]
+    fn test_chunk_segments_fits_in_one_chunk() {
+        let segments = vec![segment("aaaa"), segment("bbbb")];
+
+        let chunks = chunk_segments(&segments, 10, 1);
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].len(), 2);
+    }
+
+    #[test]
+    fn test_chunk_segments_with_overlap() {
+        // Every segment is 2 tokens long
+        let segments = ["aaaaaaaa", "bbbbbbbb", "cccccccc", "dddddddd", "eeeeeeee"]
+            .into_iter()
+            .map(segment)
+            .collect::<Vec<_>>();
+
+        let chunks = chunk_segments(&segments, 4, 2)
+            .into_iter()
+            .map(|chunk| chunk.into_iter().map(|s| s.text).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            chunks,
+            vec![
+                vec!["aaaaaaaa", "bbbbbbbb"],
+                vec!["bbbbbbbb", "cccccccc"],
+                vec!["cccccccc", "dddddddd"],
+                vec!["dddddddd", "eeeeeeee"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chunk_segments_oversized_segment() {
+        let segments = vec![segment("aaaa"), segment(&"b".repeat(100)), segment("cccc")];
+
+        let chunks = chunk_segments(&segments, 4, 0);
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[1][0].text.len(), 100);
+    }
+
+    #[test]
+    fn test_chunk_segments_empty() {
+        assert!(chunk_segments(&[], 4, 1).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_summarize_segments_condenses_partial_summaries() {
+        let text_generation = FakeTextGeneration {
+            chunk_tokens: 400,
+            condensed_tokens: 100,
+        };
+
+        let summary = summarize_segments(
+            &text_generation,
+            &long_transcript(),
+            Language::Auto,
+            0,
+            None,
+            |_| Ok(()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(summary, "merged");
+    }
+
+    #[tokio::test]
+    async fn test_summarize_segments_partial_summaries_too_long() {
+        let text_generation = FakeTextGeneration {
+            chunk_tokens: 400,
+            condensed_tokens: 300,
+        };
+
+        let result = summarize_segments(
+            &text_generation,
+            &long_transcript(),
+            Language::Auto,
+            0,
+            None,
+            |_| Ok(()),
+        )
+        .await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,340 @@
+use crate::features::summarize::audio::SAMPLE_RATE;
+
+/// 30 ms analysis frames.
+const FRAME_SIZE: usize = SAMPLE_RATE * 30 / 1000;
+/// Frames louder than the noise floor by this margin count as speech.
+const NOISE_MARGIN_DB: f32 = 10.0;
+/// Bounds for the speech threshold, so recordings that are all silence or all speech still
+/// get a sensible threshold.
+const MIN_THRESHOLD_DB: f32 = -55.0;
+const MAX_THRESHOLD_DB: f32 = -35.0;
+/// Pauses shorter than this stay inside a speech region.
+const MIN_SILENCE: usize = SAMPLE_RATE;
+/// Bursts shorter than this (clicks, bumps) are ignored.
+const MIN_SPEECH: usize = SAMPLE_RATE / 4;
+/// Audio kept around each region so word onsets and endings are not clipped.
+const PADDING: usize = SAMPLE_RATE * 3 / 10;
+/// Silence inserted between regions when they are joined for transcription.
+const REGION_GAP: usize = SAMPLE_RATE / 5;
+
+/// Range of samples, `start..end`, that contains speech.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeechRegion {
+    pub start: usize,
+    pub end: usize,
+}
+
+fn frame_energy(frame: &[f32]) -> f32 {
+    let rms = (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt();
+    20.0 * rms.max(1e-10).log10()
+}
+
+/// Energy-based voice activity detection. The threshold adapts to the noise floor of the
+/// recording, estimated from its quietest frames.
+pub fn detect_speech(samples: &[f32]) -> Vec<SpeechRegion> {
+    let mut detector = SpeechDetector::default();
+    detector.push(samples);
+    detector.finish()
+}
+
+/// [`detect_speech`] fed chunk by chunk. Only one energy value per frame is kept, the noise
+/// floor needs the whole recording before any region can be decided.
+#[derive(Default)]
+pub struct SpeechDetector {
+    energies: Vec<f32>,
+    /// Samples of a frame that continues in the next chunk
+    pending: Vec<f32>,
+    length: usize,
+}
+
+impl SpeechDetector {
+    pub fn push(&mut self, chunk: &[f32]) {
+        self.length += chunk.len();
+
+        let mut chunk = chunk;
+        if !self.pending.is_empty() {
+            let missing = (FRAME_SIZE - self.pending.len()).min(chunk.len());
+            self.pending.extend_from_slice(&chunk[..missing]);
+            chunk = &chunk[missing..];
+            if self.pending.len() < FRAME_SIZE {
+                return;
+            }
+            self.energies.push(frame_energy(&self.pending));
+            self.pending.clear();
+        }
+
+        let frames = chunk.chunks_exact(FRAME_SIZE);
+        self.pending.extend_from_slice(frames.remainder());
+        self.energies.extend(frames.map(frame_energy));
+    }
+
+    pub fn finish(mut self) -> Vec<SpeechRegion> {
+        if !self.pending.is_empty() {
+            self.energies.push(frame_energy(&self.pending));
+        }
+        let energies = self.energies;
+        let length = self.length;
+        if energies.is_empty() {
+            return Vec::new();
+        }
+
+        let mut sorted = energies.clone();
+        sorted.sort_by(f32::total_cmp);
+        let noise_floor = sorted[sorted.len() / 10];
+        let threshold = (noise_floor + NOISE_MARGIN_DB).clamp(MIN_THRESHOLD_DB, MAX_THRESHOLD_DB);
+
+        let mut regions: Vec<SpeechRegion> = Vec::new();
+        for (index, energy) in energies.iter().enumerate() {
+            if *energy <= threshold {
+                continue;
+            }
+
+            let start = index * FRAME_SIZE;
+            let end = (start + FRAME_SIZE).min(length);
+            match regions.last_mut() {
+                Some(region) if start - region.end < MIN_SILENCE => region.end = end,
+                _ => regions.push(SpeechRegion { start, end }),
+            }
+        }
+
+        let mut padded: Vec<SpeechRegion> = Vec::new();
+        for region in regions
+            .into_iter()
+            .filter(|r| r.end - r.start >= MIN_SPEECH)
+        {
+            let start = region.start.saturating_sub(PADDING);
+            let end = (region.end + PADDING).min(length);
+            match padded.last_mut() {
+                Some(previous) if start <= previous.end => previous.end = end,
+                _ => padded.push(SpeechRegion { start, end }),
+            }
+        }
+
+        padded
+    }
+}
+
+/// Splits speech regions into windows of at most `window` samples of speech, so a long
+/// recording can be transcribed a few minutes at a time. Regions longer than a window are cut.
+pub fn speech_windows(regions: &[SpeechRegion], window: usize) -> Vec<Vec<SpeechRegion>> {
+    let mut windows = Vec::new();
+    let mut current = Vec::new();
+    let mut length = 0;
+    for region in regions {
+        let mut start = region.start;
+        while start < region.end {
+            if length == window {
+                windows.push(std::mem::take(&mut current));
+                length = 0;
+            }
+            let end = region.end.min(start + window - length);
+            current.push(SpeechRegion { start, end });
+            length += end - start;
+            start = end;
+        }
+    }
+    if !current.is_empty() {
+        windows.push(current);
+    }
+
+    windows
+}
+
+/// Speech regions of a recording joined into one buffer, with a table to map positions in
+/// the joined audio back to the original recording.
+pub struct SpeechAudio {
+    pub samples: Vec<f32>,
+    /// `(joined_start, original_start, length)` of every region, in samples
+    spans: Vec<(usize, usize, usize)>,
+}
+
+impl SpeechAudio {
+    pub fn new(samples: &[f32], regions: &[SpeechRegion]) -> Self {
+        let mut builder = SpeechAudioBuilder::new(regions.to_vec());
+        builder.push(samples);
+        builder.finish()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Converts a timestamp in seconds from the joined audio to the original recording.
+    /// Timestamps that fall into the gap between two regions snap to the end of the first.
+    pub fn to_original_time(&self, seconds: f64) -> f64 {
+        let position = (seconds.max(0.0) * SAMPLE_RATE as f64) as usize;
+        let span = self
+            .spans
+            .iter()
+            .rev()
+            .find(|(joined_start, _, _)| *joined_start <= position)
+            .or(self.spans.first());
+
+        match span {
+            Some((joined_start, original_start, length)) => {
+                let offset = position.saturating_sub(*joined_start).min(*length);
+                (original_start + offset) as f64 / SAMPLE_RATE as f64
+            }
+            None => seconds,
+        }
+    }
+}
+
+/// Builds [`SpeechAudio`] from the recording chunk by chunk, keeping only the speech.
+pub struct SpeechAudioBuilder {
+    regions: Vec<SpeechRegion>,
+    audio: SpeechAudio,
+    /// Position of the next chunk in the original recording
+    position: usize,
+    /// First region that is not complete yet
+    next: usize,
+}
+
+impl SpeechAudioBuilder {
+    pub fn new(regions: Vec<SpeechRegion>) -> Self {
+        SpeechAudioBuilder {
+            audio: SpeechAudio {
+                samples: Vec::new(),
+                spans: Vec::with_capacity(regions.len()),
+            },
+            regions,
+            position: 0,
+            next: 0,
+        }
+    }
+
+    pub fn push(&mut self, chunk: &[f32]) {
+        let chunk_end = self.position + chunk.len();
+
+        while let Some(region) = self.regions.get(self.next) {
+            if region.start >= chunk_end {
+                break;
+            }
+
+            let start = region.start.max(self.position);
+            let end = region.end.min(chunk_end);
+            if start == region.start && start < end {
+                if !self.audio.spans.is_empty() {
+                    let joined_length = self.audio.samples.len() + REGION_GAP;
+                    self.audio.samples.resize(joined_length, 0.0);
+                }
+                self.audio.spans.push((
+                    self.audio.samples.len(),
+                    region.start,
+                    region.end - region.start,
+                ));
+            }
+            if start < end {
+                self.audio
+                    .samples
+                    .extend_from_slice(&chunk[start - self.position..end - self.position]);
+            }
+
+            if region.end > chunk_end {
+                break;
+            }
+            self.next += 1;
+        }
+
+        self.position = chunk_end;
+    }
+
+    pub fn finish(self) -> SpeechAudio {
+        self.audio
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(seconds: usize) -> Vec<f32> {
+        (0..seconds * SAMPLE_RATE)
+            .map(|i| 0.5 * (i as f32 * 440.0 * std::f32::consts::TAU / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_speech() {
+        let mut samples = vec![0.0; SAMPLE_RATE];
+        samples.extend(tone(2));
+        samples.extend(vec![0.0; 3 * SAMPLE_RATE]);
+
+        let regions = detect_speech(&samples);
+
+        assert_eq!(regions.len(), 1);
+        assert!(regions[0].start < SAMPLE_RATE - PADDING / 2);
+        assert_eq!(regions[0].end, 3 * SAMPLE_RATE + PADDING);
+    }
+
+    #[test]
+    fn test_detect_speech_silence() {
+        assert!(detect_speech(&vec![0.0; 5 * SAMPLE_RATE]).is_empty());
+        assert!(detect_speech(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_chunked_detection() {
+        let mut samples = vec![0.0; SAMPLE_RATE];
+        samples.extend(tone(2));
+        samples.extend(vec![0.0; 3 * SAMPLE_RATE]);
+        samples.extend(tone(1));
+
+        let mut detector = SpeechDetector::default();
+        for chunk in samples.chunks(1000) {
+            detector.push(chunk);
+        }
+        let regions = detector.finish();
+        assert_eq!(regions, detect_speech(&samples));
+
+        let mut builder = SpeechAudioBuilder::new(regions.clone());
+        for chunk in samples.chunks(777) {
+            builder.push(chunk);
+        }
+        assert_eq!(
+            builder.finish().samples,
+            SpeechAudio::new(&samples, &regions).samples
+        );
+    }
+
+    #[test]
+    fn test_to_original_time() {
+        let samples = vec![0.0; 6 * SAMPLE_RATE];
+        let regions = [
+            SpeechRegion {
+                start: SAMPLE_RATE,
+                end: 2 * SAMPLE_RATE,
+            },
+            SpeechRegion {
+                start: 4 * SAMPLE_RATE,
+                end: 5 * SAMPLE_RATE,
+            },
+        ];
+
+        let speech = SpeechAudio::new(&samples, &regions);
+        let second_start = (SAMPLE_RATE + REGION_GAP) as f64 / SAMPLE_RATE as f64;
+
+        assert_eq!(speech.samples.len(), 2 * SAMPLE_RATE + REGION_GAP);
+        assert_eq!(speech.to_original_time(0.5), 1.5);
+        assert_eq!(speech.to_original_time(1.1), 2.0);
+        assert_eq!(speech.to_original_time(second_start), 4.0);
+        assert_eq!(speech.to_original_time(second_start + 0.25), 4.25);
+    }
+
+    #[test]
+    fn test_speech_windows() {
+        let region = |start: usize, end: usize| SpeechRegion { start, end };
+        let regions = [region(0, 4), region(10, 13), region(20, 30)];
+
+        assert_eq!(
+            speech_windows(&regions, 5),
+            vec![
+                vec![region(0, 4), region(10, 11)],
+                vec![region(11, 13), region(20, 23)],
+                vec![region(23, 28)],
+                vec![region(28, 30)],
+            ]
+        );
+        assert!(speech_windows(&[region(0, 0)], 5).is_empty());
+    }
+}
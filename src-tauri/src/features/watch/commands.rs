@@ -0,0 +1,70 @@
+use anyhow::Context;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::ErrorCode,
+    features::{summarize::language::Language, watch::entities::WatchedFolder},
+    state::AppState,
+};
+
+#[tauri::command]
+pub async fn get_watched_folders(
+    state: State<'_, AppState>,
+) -> Result<Vec<WatchedFolder>, ErrorCode> {
+    let folders = state
+        .folder_watcher
+        .get_all()
+        .await
+        .context("Failed to fetch watched folders")?;
+
+    Ok(folders)
+}
+
+#[tauri::command]
+pub async fn add_watched_folder(
+    state: State<'_, AppState>,
+    path: String,
+    language: Language,
+    template: Option<String>,
+) -> Result<WatchedFolder, ErrorCode> {
+    if !tokio::fs::metadata(&path)
+        .await
+        .map(|m| m.is_dir())
+        .unwrap_or(false)
+    {
+        return Err(ErrorCode::invalid_input(format!(
+            "{} is not a directory",
+            path
+        )));
+    }
+
+    let folder = state
+        .folder_watcher
+        .add(path, language, template)
+        .await
+        .context("Failed to watch folder")?;
+
+    Ok(folder)
+}
+
+#[tauri::command]
+pub async fn remove_watched_folder(
+    state: State<'_, AppState>,
+    folder_id: Uuid,
+) -> Result<(), ErrorCode> {
+    let removed = state
+        .folder_watcher
+        .remove(folder_id)
+        .await
+        .context("Failed to stop watching folder")?;
+
+    if !removed {
+        return Err(ErrorCode::NotFound(format!(
+            "Watched folder with id {} not found",
+            folder_id
+        )));
+    }
+
+    Ok(())
+}
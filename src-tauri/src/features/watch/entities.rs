@@ -0,0 +1,19 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+use crate::features::summarize::language::Language;
+
+#[derive(Clone, FromRow, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchedFolder {
+    pub id: Uuid,
+    pub path: String,
+    /// Language new recordings in this folder are transcribed in
+    pub language: Language,
+    /// Note structure the summaries of this folder should follow
+    pub template: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
@@ -7,9 +7,16 @@ pub mod state;
 pub mod utils;
 
 use crate::features::chat::commands::*;
+use crate::features::glossary::commands::*;
+use crate::features::job::commands::*;
 use crate::features::model::commands::*;
+use crate::features::speaker::commands::*;
 use crate::features::summarize::commands::*;
+use crate::features::watch::commands::*;
 use crate::state::download::DownloadManager;
+use crate::state::job::JobManager;
+use crate::state::watcher::FolderWatcher;
+use crate::state::whisper::WhisperCache;
 use crate::state::AppState;
 
 use std::panic;
@@ -74,6 +81,9 @@ pub fn run() {
             // Setup application state
             app.manage(AppState {
                 download_manager: DownloadManager::new(app.handle().clone()),
+                job_manager: JobManager::new(app.handle().clone()),
+                folder_watcher: FolderWatcher::new(app.handle().clone()),
+                whisper_cache: WhisperCache::new(app.handle().clone()),
             });
 
             // Setup database
@@ -113,6 +123,18 @@ pub fn run() {
             });
             app.manage(db_pool);
 
+            // Pick up jobs interrupted by the previous shutdown and start watching folders
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app_handle.state::<AppState>();
+                if let Err(e) = state.job_manager.resume().await {
+                    tracing::error!(error = %e, "Failed to resume unfinished jobs");
+                }
+                if let Err(e) = state.folder_watcher.start().await {
+                    tracing::error!(error = %e, "Failed to start folder watcher");
+                }
+            });
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -123,13 +145,43 @@ pub fn run() {
             get_speech_to_text_models,
             download_speech_to_text_model,
             set_text_generation_api_key,
+            get_transcription_settings,
+            set_transcription_settings,
             get_text_generation_models,
+            get_local_text_generation_models,
+            download_text_generation_model,
             // Summarize command
             get_languages,
             get_summary,
             get_summaries,
+            get_transcript,
             delete_summary,
-            summarize
+            regenerate_summary,
+            cancel_summary_regeneration,
+            probe_media,
+            summarize,
+            summarize_batch,
+            // Job commands
+            list_jobs,
+            cancel_job,
+            retry_job,
+            get_batch_report,
+            // Watch folder commands
+            get_watched_folders,
+            add_watched_folder,
+            remove_watched_folder,
+            // Speaker commands
+            get_speakers,
+            rename_speaker,
+            merge_speakers,
+            get_voice_profiles,
+            enroll_speaker,
+            delete_voice_profile,
+            // Glossary commands
+            get_glossaries,
+            create_glossary,
+            update_glossary,
+            delete_glossary
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -4,7 +4,8 @@ use dashmap::DashMap;
 use futures_util::StreamExt;
 use reqwest::header;
 use serde::{Deserialize, Serialize};
-use sha1::{Digest, Sha1};
+use sha1::{digest, Digest, Sha1};
+use sha2::Sha256;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
@@ -84,27 +85,34 @@ pub enum DownloadStatus {
 #[serde(tag = "type", content = "value")]
 pub enum Checksum {
     Sha1(String),
+    Sha256(String),
 }
 
 impl Checksum {
     pub async fn validate(&self, file: &mut BufReader<File>) -> Result<bool> {
         match self {
-            Checksum::Sha1(expected) => {
-                let mut hasher = Sha1::new();
-                let mut buffer = [0u8; 8192];
-
-                loop {
-                    let n = file.read(&mut buffer).await?;
-                    if n == 0 {
-                        break;
-                    }
-                    hasher.update(&buffer[..n]);
-                }
+            Checksum::Sha1(expected) => Ok(digest_file::<Sha1>(file).await? == *expected),
+            Checksum::Sha256(expected) => Ok(digest_file::<Sha256>(file).await? == *expected),
+        }
+    }
+}
 
-                Ok(format!("{:x}", hasher.finalize()) == *expected)
-            }
+pub async fn digest_file<D: Digest>(file: &mut BufReader<File>) -> Result<String>
+where
+    digest::Output<D>: std::fmt::LowerHex,
+{
+    let mut hasher = D::new();
+    let mut buffer = [0u8; 8192];
+
+    loop {
+        let n = file.read(&mut buffer).await?;
+        if n == 0 {
+            break;
         }
+        hasher.update(&buffer[..n]);
     }
+
+    Ok(format!("{:x}", hasher.finalize()))
 }
 
 pub struct DownloadManager {
@@ -0,0 +1,386 @@
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{bail, Context, Error, Result};
+use dashmap::{mapref::entry::Entry, DashMap};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::{select, sync::Semaphore};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::{
+    features::{
+        job::{
+            batch::{collect_files, get_batch_report, BatchReport},
+            entities::{Job, JobOptions, JobStage, JobStatus},
+        },
+        summarize::{
+            audio::validate_file_type,
+            entities::{SourceFile, SourceFiles, Summary},
+            pipeline::{regenerate_summary, run_summarize_job, save_job_progress, Cancelled},
+        },
+    },
+    utils::tauri::get_settings_store,
+};
+
+/// Whisper is memory hungry, so by default jobs run one at a time and the rest wait in the queue.
+const DEFAULT_CONCURRENT_JOBS: usize = 1;
+const MAX_CONCURRENT_JOBS: usize = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "payload", rename_all = "kebab-case")]
+pub enum JobEvent {
+    StatusChanged {
+        id: Uuid,
+        status: JobStatus,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    BatchProgress {
+        batch_id: Uuid,
+        total: usize,
+        completed: usize,
+        failed: usize,
+        cancelled: usize,
+    },
+}
+
+pub struct JobManager {
+    tokens: Arc<DashMap<Uuid, CancellationToken>>,
+    /// Summaries being regenerated, by summary id
+    regenerations: DashMap<Uuid, CancellationToken>,
+    semaphore: Arc<Semaphore>,
+    app: AppHandle,
+}
+
+impl JobManager {
+    pub fn new(app: AppHandle) -> Self {
+        let concurrency = get_settings_store(&app)
+            .ok()
+            .and_then(|store| store.get("summarize.maxConcurrentJobs"))
+            .and_then(|value| value.as_u64())
+            .map(|value| (value as usize).clamp(1, MAX_CONCURRENT_JOBS))
+            .unwrap_or(DEFAULT_CONCURRENT_JOBS);
+
+        JobManager {
+            tokens: Arc::new(DashMap::new()),
+            regenerations: DashMap::new(),
+            semaphore: Arc::new(Semaphore::new(concurrency)),
+            app,
+        }
+    }
+
+    fn database(&self) -> SqlitePool {
+        self.app.state::<SqlitePool>().inner().clone()
+    }
+
+    /// Persists a new job and queues it for processing.
+    pub async fn enqueue(&self, file_path: String, options: JobOptions) -> Result<Job> {
+        let job = self.insert(file_path, &options, None, None).await?;
+        self.spawn(job.clone());
+
+        Ok(job)
+    }
+
+    /// Queues every file in `paths`, expanding directories into the files they contain.
+    /// Files that fail validation are recorded as failed jobs so they show up in the report.
+    pub async fn enqueue_batch(
+        &self,
+        paths: Vec<PathBuf>,
+        options: JobOptions,
+    ) -> Result<BatchReport> {
+        let files = collect_files(&paths).await?;
+        if files.is_empty() {
+            bail!("No files found to summarize");
+        }
+
+        let batch_id = Uuid::new_v4();
+        for file in files {
+            let file_path = file.to_string_lossy().to_string();
+            match validate_file_type(&file).await {
+                Ok(()) => {
+                    let job = self
+                        .insert(file_path, &options, Some(batch_id), None)
+                        .await?;
+                    self.spawn(job);
+                }
+                Err(e) => {
+                    info!(path=%file_path, error=%e, "Skipping file in batch");
+                    let error = format!("{:#}", e);
+                    self.insert(file_path, &options, Some(batch_id), Some(error))
+                        .await?;
+                }
+            }
+        }
+
+        get_batch_report(&self.database(), batch_id).await
+    }
+
+    pub async fn get_batch(&self, batch_id: Uuid) -> Result<BatchReport> {
+        get_batch_report(&self.database(), batch_id).await
+    }
+
+    /// Inserts a job row. A job created with an `error` is stored as failed and never runs.
+    async fn insert(
+        &self,
+        file_path: String,
+        options: &JobOptions,
+        batch_id: Option<Uuid>,
+        error: Option<String>,
+    ) -> Result<Job> {
+        let job_id = Uuid::new_v4();
+        let status = match error {
+            Some(_) => JobStatus::Failed,
+            None => JobStatus::Queued,
+        };
+        let sources = (!options.appended_files.is_empty()).then(|| {
+            SourceFiles(
+                std::iter::once(&file_path)
+                    .chain(&options.appended_files)
+                    .map(|path| SourceFile {
+                        path: path.clone(),
+                        offset: None,
+                        duration: None,
+                    })
+                    .collect(),
+            )
+        });
+
+        sqlx::query(
+            "INSERT INTO jobs (id, batch_id, file_path, language, template, transcription_mode, keep_original_transcript, audio_selection, sources, start_time, end_time, status, stage, error) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(job_id)
+        .bind(batch_id)
+        .bind(&file_path)
+        .bind(options.language)
+        .bind(&options.template)
+        .bind(options.transcription_mode)
+        .bind(options.keep_original_transcript)
+        .bind(&options.audio_selection)
+        .bind(&sources)
+        .bind(options.start_time)
+        .bind(options.end_time)
+        .bind(status)
+        .bind(JobStage::Pending)
+        .bind(&error)
+        .execute(&self.database())
+        .await
+        .context("Failed to insert job into database")?;
+
+        for glossary_id in &options.glossary_ids {
+            // Unknown glossaries are skipped instead of failing the whole run
+            sqlx::query(
+                "INSERT OR IGNORE INTO job_glossaries (job_id, glossary_id) SELECT ?, id FROM glossaries WHERE id = ?",
+            )
+            .bind(job_id)
+            .bind(glossary_id)
+            .execute(&self.database())
+            .await
+            .context("Failed to link glossary to job")?;
+        }
+
+        self.get(job_id).await
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<Job> {
+        let job = sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = ?")
+            .bind(id)
+            .fetch_one(&self.database())
+            .await
+            .context("Failed to fetch job from database")?;
+
+        Ok(job)
+    }
+
+    pub async fn get_all(&self) -> Result<Vec<Job>> {
+        let jobs = sqlx::query_as::<_, Job>("SELECT * FROM jobs ORDER BY created_at DESC")
+            .fetch_all(&self.database())
+            .await
+            .context("Failed to fetch jobs from database")?;
+
+        Ok(jobs)
+    }
+
+    /// Cancels a queued or running job. Returns `false` if the job had already finished.
+    pub async fn cancel(&self, id: Uuid) -> Result<bool> {
+        let mut job = self.get(id).await?;
+        if job.status.is_finished() {
+            return Ok(false);
+        }
+
+        match self.tokens.get(&id) {
+            Some(token) => token.cancel(),
+            None => {
+                // Not picked up by this session (e.g. recovery failed), so mark it directly
+                job.status = JobStatus::Cancelled;
+                save_job_progress(&self.database(), &job).await?;
+                self.emit(JobEvent::StatusChanged {
+                    id,
+                    status: JobStatus::Cancelled,
+                    error: None,
+                });
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Writes the summary of a recording again from its stored transcript. It waits for a slot
+    /// in the queue like a job does, so two models are never loaded side by side. Returns
+    /// `None` if the summary does not exist.
+    pub async fn regenerate(&self, summary_id: Uuid) -> Result<Option<Summary>> {
+        let token = CancellationToken::new();
+        match self.regenerations.entry(summary_id) {
+            Entry::Occupied(_) => bail!("The summary is already being regenerated"),
+            Entry::Vacant(entry) => {
+                entry.insert(token.clone());
+            }
+        }
+
+        let result = async {
+            let _permit = select! {
+                _ = token.cancelled() => return Err(Error::new(Cancelled)),
+                permit = self.semaphore.acquire() => permit.context("Job queue was closed")?,
+            };
+
+            regenerate_summary(&self.app, &self.database(), summary_id, &token).await
+        }
+        .await;
+        self.regenerations.remove(&summary_id);
+
+        result
+    }
+
+    /// Cancels a running summary regeneration. Returns `false` if none is running.
+    pub fn cancel_regeneration(&self, summary_id: Uuid) -> bool {
+        match self.regenerations.get(&summary_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Queues a failed or cancelled job again. It resumes from its last completed stage.
+    pub async fn retry(&self, id: Uuid) -> Result<Option<Job>> {
+        let mut job = self.get(id).await?;
+        if !matches!(job.status, JobStatus::Failed | JobStatus::Cancelled) {
+            return Ok(None);
+        }
+
+        job.status = JobStatus::Queued;
+        job.error = None;
+        save_job_progress(&self.database(), &job).await?;
+        self.spawn(job.clone());
+
+        Ok(Some(job))
+    }
+
+    /// Queues every job that was still queued or running when the app last closed.
+    pub async fn resume(&self) -> Result<()> {
+        let jobs = sqlx::query_as::<_, Job>(
+            "SELECT * FROM jobs WHERE status IN (?, ?) ORDER BY created_at ASC",
+        )
+        .bind(JobStatus::Queued)
+        .bind(JobStatus::Running)
+        .fetch_all(&self.database())
+        .await
+        .context("Failed to fetch unfinished jobs")?;
+
+        for job in jobs {
+            info!(id=%job.id, stage=%job.stage, "Resuming job");
+            self.spawn(job);
+        }
+
+        Ok(())
+    }
+
+    fn spawn(&self, mut job: Job) {
+        let token = CancellationToken::new();
+        self.tokens.insert(job.id, token.clone());
+
+        let tokens = Arc::clone(&self.tokens);
+        let semaphore = Arc::clone(&self.semaphore);
+        let database = self.database();
+        let app = self.app.clone();
+
+        tokio::spawn(async move {
+            let emit = |event: JobEvent| {
+                if let Err(e) = app.emit("job", &event) {
+                    error!("Failed to emit job event: {}", e);
+                }
+            };
+
+            let result = async {
+                let _permit = select! {
+                    _ = token.cancelled() => return Err(Error::new(Cancelled)),
+                    permit = semaphore.acquire() => permit.context("Job queue was closed")?,
+                };
+
+                job.status = JobStatus::Running;
+                save_job_progress(&database, &job).await?;
+                emit(JobEvent::StatusChanged {
+                    id: job.id,
+                    status: JobStatus::Running,
+                    error: None,
+                });
+
+                run_summarize_job(&app, &database, &mut job, &token).await
+            }
+            .await;
+
+            match result {
+                Ok(_) => {
+                    info!(id=%job.id, "Job completed");
+                    job.status = JobStatus::Completed;
+                    job.error = None;
+                }
+                Err(_) if token.is_cancelled() => {
+                    info!(id=%job.id, "Job cancelled");
+                    job.status = JobStatus::Cancelled;
+                }
+                Err(e) => {
+                    error!(id=%job.id, error=%e, "Job failed");
+                    job.status = JobStatus::Failed;
+                    job.error = Some(format!("{:#}", e));
+                }
+            }
+
+            // Removed before the final status is saved: once it is, the job can be retried,
+            // and the token of the retried job must not be removed in its place
+            tokens.remove(&job.id);
+
+            if let Err(e) = save_job_progress(&database, &job).await {
+                error!(id=%job.id, error=%e, "Failed to save job status");
+            }
+            emit(JobEvent::StatusChanged {
+                id: job.id,
+                status: job.status,
+                error: job.error.clone(),
+            });
+
+            if let Some(batch_id) = job.batch_id {
+                match get_batch_report(&database, batch_id).await {
+                    Ok(report) => emit(JobEvent::BatchProgress {
+                        batch_id,
+                        total: report.total,
+                        completed: report.completed,
+                        failed: report.failed,
+                        cancelled: report.cancelled,
+                    }),
+                    Err(e) => error!(%batch_id, error=%e, "Failed to fetch batch progress"),
+                }
+            }
+        });
+    }
+
+    fn emit(&self, event: JobEvent) {
+        if let Err(e) = self.app.emit("job", &event) {
+            error!("Failed to emit job event: {}", e);
+        }
+    }
+}
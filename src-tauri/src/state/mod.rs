@@ -1,7 +1,16 @@
 pub mod download;
+pub mod job;
+pub mod watcher;
+pub mod whisper;
 
 use download::DownloadManager;
+use job::JobManager;
+use watcher::FolderWatcher;
+use whisper::WhisperCache;
 
 pub struct AppState {
     pub download_manager: DownloadManager,
+    pub job_manager: JobManager,
+    pub folder_watcher: FolderWatcher,
+    pub whisper_cache: WhisperCache,
 }
@@ -0,0 +1,392 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use notify::{event::ModifyKind, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use sha2::Sha256;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tokio::{fs::File, io::BufReader};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    features::{
+        job::{batch::collect_files, entities::JobOptions},
+        summarize::{audio::validate_file_type, language::Language},
+        watch::entities::WatchedFolder,
+    },
+    state::{download::digest_file, AppState},
+};
+
+/// How long a file has to stay untouched before it is considered fully written.
+const DEBOUNCE: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+struct PendingFile {
+    folder_id: Uuid,
+    last_change: Instant,
+    size: Option<u64>,
+}
+
+pub struct FolderWatcher {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    folders: Arc<DashMap<PathBuf, WatchedFolder>>,
+    pending: Arc<DashMap<PathBuf, PendingFile>>,
+    app: AppHandle,
+}
+
+impl FolderWatcher {
+    pub fn new(app: AppHandle) -> Self {
+        let folders: Arc<DashMap<PathBuf, WatchedFolder>> = Arc::new(DashMap::new());
+        let pending: Arc<DashMap<PathBuf, PendingFile>> = Arc::new(DashMap::new());
+
+        let event_folders = Arc::clone(&folders);
+        let event_pending = Arc::clone(&pending);
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!(error=%e, "File watcher error");
+                    return;
+                }
+            };
+
+            if !matches!(
+                event.kind,
+                EventKind::Create(_) | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Name(_))
+            ) {
+                return;
+            }
+
+            for path in event.paths {
+                let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+                    continue;
+                };
+                let parent = canonical_path(parent);
+                let Some(folder_id) = event_folders.get(&parent).map(|folder| folder.id) else {
+                    continue;
+                };
+
+                track(&event_pending, parent.join(name), folder_id);
+            }
+        })
+        .inspect_err(|e| error!(error=%e, "Failed to create file watcher"))
+        .ok();
+
+        FolderWatcher {
+            watcher: Mutex::new(watcher),
+            folders,
+            pending,
+            app,
+        }
+    }
+
+    fn database(&self) -> SqlitePool {
+        self.app.state::<SqlitePool>().inner().clone()
+    }
+
+    /// Starts watching every saved folder and queues recordings added while the app was closed.
+    pub async fn start(&self) -> Result<()> {
+        let pending = Arc::clone(&self.pending);
+        let folders = Arc::clone(&self.folders);
+        let app = self.app.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                process_pending(&app, &folders, &pending).await;
+            }
+        });
+
+        for folder in self.get_all().await? {
+            let path = canonical_path(Path::new(&folder.path));
+            if let Err(e) = self.watch(folder.clone()) {
+                error!(path=%folder.path, error=%e, "Failed to watch folder");
+                continue;
+            }
+
+            let since = folder.created_at.and_utc();
+            for file in collect_files(&[path]).await.unwrap_or_default() {
+                let modified = tokio::fs::metadata(&file)
+                    .await
+                    .and_then(|m| m.modified())
+                    .map(DateTime::<Utc>::from);
+                if modified.is_ok_and(|modified| modified > since) {
+                    track(&self.pending, file, folder.id);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_all(&self) -> Result<Vec<WatchedFolder>> {
+        let folders = sqlx::query_as::<_, WatchedFolder>(
+            "SELECT * FROM watched_folders ORDER BY created_at ASC",
+        )
+        .fetch_all(&self.database())
+        .await
+        .context("Failed to fetch watched folders from database")?;
+
+        Ok(folders)
+    }
+
+    /// Watches a folder for new recordings. Files already in the folder are left alone.
+    pub async fn add(
+        &self,
+        path: String,
+        language: Language,
+        template: Option<String>,
+    ) -> Result<WatchedFolder> {
+        let database = self.database();
+        let folder_id = Uuid::new_v4();
+        let path = tokio::fs::canonicalize(&path)
+            .await
+            .with_context(|| format!("Failed to resolve folder {}", path))?
+            .to_string_lossy()
+            .to_string();
+
+        sqlx::query(
+            "INSERT INTO watched_folders (id, path, language, template) VALUES (?, ?, ?, ?)",
+        )
+        .bind(folder_id)
+        .bind(&path)
+        .bind(language)
+        .bind(&template)
+        .execute(&database)
+        .await
+        .context("Failed to insert watched folder into database")?;
+
+        let folder =
+            sqlx::query_as::<_, WatchedFolder>("SELECT * FROM watched_folders WHERE id = ?")
+                .bind(folder_id)
+                .fetch_one(&database)
+                .await
+                .context("Failed to fetch watched folder from database")?;
+
+        if let Err(e) = self.watch(folder.clone()) {
+            sqlx::query("DELETE FROM watched_folders WHERE id = ?")
+                .bind(folder_id)
+                .execute(&database)
+                .await
+                .context("Failed to delete watched folder")?;
+            return Err(e);
+        }
+
+        info!(path=%folder.path, "Watching folder");
+        Ok(folder)
+    }
+
+    /// Stops watching a folder. Returns `false` if no folder with this id exists.
+    pub async fn remove(&self, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM watched_folders WHERE id = ?")
+            .bind(id)
+            .execute(&self.database())
+            .await
+            .context("Failed to delete watched folder")?;
+
+        let path = self
+            .folders
+            .iter()
+            .find(|folder| folder.id == id)
+            .map(|folder| folder.key().clone());
+        if let Some(path) = path {
+            self.folders.remove(&path);
+            self.pending.retain(|_, file| file.folder_id != id);
+            if let Some(watcher) = self.watcher.lock().unwrap().as_mut() {
+                if let Err(e) = watcher.unwatch(&path) {
+                    warn!(path=%path.display(), error=%e, "Failed to unwatch folder");
+                }
+            }
+        }
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    fn watch(&self, folder: WatchedFolder) -> Result<()> {
+        let path = canonical_path(Path::new(&folder.path));
+        let mut watcher = self.watcher.lock().unwrap();
+        watcher
+            .as_mut()
+            .context("File watching is not available on this system")?
+            .watch(&path, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", path.display()))?;
+        self.folders.insert(path, folder);
+
+        Ok(())
+    }
+}
+
+/// Resolves symlinks and aliases like macOS' `/var` for `/private/var`, so event paths match
+/// the watched folders. Paths that cannot be resolved are used as they are.
+fn canonical_path(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn track(pending: &DashMap<PathBuf, PendingFile>, path: PathBuf, folder_id: Uuid) {
+    let size = std::fs::metadata(&path).ok().map(|m| m.len());
+    pending.insert(
+        path,
+        PendingFile {
+            folder_id,
+            last_change: Instant::now(),
+            size,
+        },
+    );
+}
+
+/// Queues files that have been quiet for [`DEBOUNCE`] and whose size did not change since the
+/// last event, which filters out recordings that are still being copied or synced.
+async fn process_pending(
+    app: &AppHandle,
+    folders: &DashMap<PathBuf, WatchedFolder>,
+    pending: &DashMap<PathBuf, PendingFile>,
+) {
+    let ready = pending
+        .iter()
+        .filter(|file| file.last_change.elapsed() >= DEBOUNCE)
+        .map(|file| file.key().clone())
+        .collect::<Vec<_>>();
+
+    for path in ready {
+        let Some((_, file)) = pending.remove(&path) else {
+            continue;
+        };
+        let size = match tokio::fs::metadata(&path).await {
+            Ok(metadata) if metadata.is_file() => metadata.len(),
+            _ => continue,
+        };
+        if file.size != Some(size) {
+            track(pending, path, file.folder_id);
+            continue;
+        }
+
+        let Some(folder) = path
+            .parent()
+            .and_then(|parent| folders.get(parent))
+            .map(|folder| folder.clone())
+        else {
+            continue;
+        };
+
+        if let Err(e) = ingest(app, &folder, &path).await {
+            error!(path=%path.display(), error=%e, "Failed to ingest file from watched folder");
+        }
+    }
+}
+
+async fn ingest(app: &AppHandle, folder: &WatchedFolder, path: &Path) -> Result<()> {
+    let path = path.to_path_buf();
+    let file_path = path.to_string_lossy().to_string();
+    let metadata = tokio::fs::metadata(&path)
+        .await
+        .context("Failed to read file metadata")?;
+    let file_size = metadata.len() as i64;
+    let modified_at = metadata
+        .modified()
+        .map(|modified| DateTime::<Utc>::from(modified).timestamp_millis())
+        .ok();
+
+    // Every file newer than the folder is checked again on startup, so unchanged files are
+    // recognized without hashing them
+    let database = app.state::<SqlitePool>();
+    let unchanged = sqlx::query_scalar::<_, String>(
+        "SELECT hash FROM ingested_files WHERE file_path = ? AND file_size = ? AND modified_at = ?",
+    )
+    .bind(&file_path)
+    .bind(file_size)
+    .bind(modified_at)
+    .fetch_optional(database.inner())
+    .await
+    .context("Failed to check ingested files")?
+    .is_some();
+    if unchanged {
+        return Ok(());
+    }
+
+    let rejected = sqlx::query_scalar::<_, String>(
+        "SELECT file_path FROM rejected_files WHERE file_path = ? AND file_size = ? AND modified_at IS ?",
+    )
+    .bind(&file_path)
+    .bind(file_size)
+    .bind(modified_at)
+    .fetch_optional(database.inner())
+    .await
+    .context("Failed to check rejected files")?
+    .is_some();
+    if rejected {
+        return Ok(());
+    }
+
+    if let Err(e) = validate_file_type(&path).await {
+        debug!(path=%path.display(), error=%e, "Ignoring unsupported file");
+        sqlx::query(
+            "INSERT OR REPLACE INTO rejected_files (file_path, file_size, modified_at) VALUES (?, ?, ?)",
+        )
+        .bind(&file_path)
+        .bind(file_size)
+        .bind(modified_at)
+        .execute(database.inner())
+        .await
+        .context("Failed to record rejected file")?;
+        return Ok(());
+    }
+
+    let file = File::open(&path).await.context("Failed to open file")?;
+    let hash = digest_file::<Sha256>(&mut BufReader::new(file))
+        .await
+        .context("Failed to hash file")?;
+
+    let already_ingested =
+        sqlx::query_scalar::<_, String>("SELECT hash FROM ingested_files WHERE hash = ?")
+            .bind(&hash)
+            .fetch_optional(database.inner())
+            .await
+            .context("Failed to check ingested files")?
+            .is_some();
+    if already_ingested {
+        debug!(path=%path.display(), "Skipping file that was already summarized");
+        sqlx::query(
+            "UPDATE ingested_files SET file_path = ?, file_size = ?, modified_at = ? WHERE hash = ?",
+        )
+        .bind(&file_path)
+        .bind(file_size)
+        .bind(modified_at)
+        .bind(&hash)
+        .execute(database.inner())
+        .await
+        .context("Failed to record ingested file")?;
+        return Ok(());
+    }
+
+    app.state::<AppState>()
+        .job_manager
+        .enqueue(
+            file_path.clone(),
+            JobOptions {
+                template: folder.template.clone(),
+                ..JobOptions::new(folder.language)
+            },
+        )
+        .await?;
+
+    sqlx::query(
+        "INSERT INTO ingested_files (hash, file_path, file_size, modified_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(&hash)
+    .bind(&file_path)
+    .bind(file_size)
+    .bind(modified_at)
+    .execute(database.inner())
+    .await
+    .context("Failed to record ingested file")?;
+
+    info!(path=%file_path, "Queued file from watched folder");
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use sysinfo::System;
+use tauri::{AppHandle, Manager};
+use tokio::sync::Mutex;
+use tracing::info;
+use whisper_rs::WhisperContext;
+
+use crate::features::model::speech_to_text::{
+    load_context, SpeechToTextModel, TranscriptionSettings,
+};
+
+/// Models that have not been used for this long are unloaded.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+/// Idle models are unloaded right away once available memory drops below this share of the
+/// total. A fixed amount would unload after every job on machines with little memory.
+const LOW_MEMORY_PERCENT: u64 = 10;
+
+/// A model loaded for another GPU setup has to be loaded again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ModelKey {
+    model: SpeechToTextModel,
+    use_gpu: bool,
+    gpu_device: u16,
+}
+
+struct CachedModel {
+    context: Arc<WhisperContext>,
+    last_used: Instant,
+}
+
+impl CachedModel {
+    /// Whether a transcription still holds a reference to the context.
+    fn in_use(&self) -> bool {
+        Arc::strong_count(&self.context) > 1
+    }
+}
+
+/// Keeps loaded whisper models in memory between transcriptions.
+pub struct WhisperCache {
+    models: Arc<Mutex<HashMap<ModelKey, CachedModel>>>,
+    app: AppHandle,
+}
+
+impl WhisperCache {
+    pub fn new(app: AppHandle) -> Self {
+        let models = Arc::new(Mutex::new(HashMap::new()));
+
+        let sweep_models = Arc::clone(&models);
+        tauri::async_runtime::spawn(async move {
+            let mut system = System::new();
+            let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                system.refresh_memory();
+                let low_memory =
+                    system.available_memory() < system.total_memory() / 100 * LOW_MEMORY_PERCENT;
+                evict(&mut *sweep_models.lock().await, low_memory);
+            }
+        });
+
+        WhisperCache { models, app }
+    }
+
+    /// Returns the loaded context for `model`, loading it from disk on a cache miss.
+    pub async fn get(
+        &self,
+        model: SpeechToTextModel,
+        settings: &TranscriptionSettings,
+    ) -> Result<Arc<WhisperContext>> {
+        let key = ModelKey {
+            model,
+            use_gpu: settings.use_gpu,
+            gpu_device: settings.gpu_device,
+        };
+        let mut models = self.models.lock().await;
+
+        if let Some(cached) = models.get_mut(&key) {
+            cached.last_used = Instant::now();
+            return Ok(Arc::clone(&cached.context));
+        }
+
+        // Only one model is kept at a time, the large ones take several gigabytes each
+        models.retain(|_, cached| cached.in_use());
+
+        let model_path = self
+            .app
+            .path()
+            .app_local_data_dir()
+            .context("Could not determine app local data directory")?
+            .join("models")
+            .join(model.filename());
+        info!(?key, "Loading whisper model");
+        let context = tokio::task::spawn_blocking(move || {
+            load_context(&model_path, key.use_gpu, key.gpu_device)
+        })
+        .await
+        .context("Whisper model loading thread panicked")??;
+
+        let context = Arc::new(context);
+        models.insert(
+            key,
+            CachedModel {
+                context: Arc::clone(&context),
+                last_used: Instant::now(),
+            },
+        );
+
+        Ok(context)
+    }
+}
+
+fn evict(models: &mut HashMap<ModelKey, CachedModel>, low_memory: bool) {
+    models.retain(|key, cached| {
+        if cached.in_use() {
+            cached.last_used = Instant::now();
+            return true;
+        }
+
+        let keep = !low_memory && cached.last_used.elapsed() < IDLE_TIMEOUT;
+        if !keep {
+            info!(?key, low_memory, "Unloading whisper model");
+        }
+        keep
+    });
+}
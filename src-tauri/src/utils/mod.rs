@@ -1 +1,2 @@
+pub mod sqlite;
 pub mod tauri;
@@ -0,0 +1,63 @@
+/// Stores an enum as TEXT using its `Display` and `FromStr` implementations.
+macro_rules! impl_sqlite_string_enum {
+    ($name:ident) => {
+        impl sqlx::Type<sqlx::Sqlite> for $name {
+            fn type_info() -> sqlx::sqlite::SqliteTypeInfo {
+                <String as sqlx::Type<sqlx::Sqlite>>::type_info()
+            }
+        }
+
+        impl<'r> sqlx::Decode<'r, sqlx::Sqlite> for $name {
+            fn decode(
+                value: sqlx::sqlite::SqliteValueRef<'r>,
+            ) -> Result<Self, sqlx::error::BoxDynError> {
+                let s = <String as sqlx::Decode<sqlx::Sqlite>>::decode(value)?;
+                <$name as std::str::FromStr>::from_str(&s)
+                    .map_err(|_| format!("invalid {} value in db: {}", stringify!($name), s).into())
+            }
+        }
+
+        impl<'q> sqlx::Encode<'q, sqlx::Sqlite> for $name {
+            fn encode_by_ref(
+                &self,
+                args: &mut Vec<sqlx::sqlite::SqliteArgumentValue<'q>>,
+            ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+                let s = self.to_string();
+                <String as sqlx::Encode<sqlx::Sqlite>>::encode(s, args)
+            }
+        }
+    };
+}
+
+/// Stores a serde type as JSON in a TEXT column.
+macro_rules! impl_sqlite_json {
+    ($name:ident) => {
+        impl sqlx::Type<sqlx::Sqlite> for $name {
+            fn type_info() -> sqlx::sqlite::SqliteTypeInfo {
+                <String as sqlx::Type<sqlx::Sqlite>>::type_info()
+            }
+        }
+
+        impl<'r> sqlx::Decode<'r, sqlx::Sqlite> for $name {
+            fn decode(
+                value: sqlx::sqlite::SqliteValueRef<'r>,
+            ) -> Result<Self, sqlx::error::BoxDynError> {
+                let s = <String as sqlx::Decode<sqlx::Sqlite>>::decode(value)?;
+                Ok(serde_json::from_str(&s)?)
+            }
+        }
+
+        impl<'q> sqlx::Encode<'q, sqlx::Sqlite> for $name {
+            fn encode_by_ref(
+                &self,
+                args: &mut Vec<sqlx::sqlite::SqliteArgumentValue<'q>>,
+            ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+                let s = serde_json::to_string(self)?;
+                <String as sqlx::Encode<sqlx::Sqlite>>::encode(s, args)
+            }
+        }
+    };
+}
+
+pub(crate) use impl_sqlite_json;
+pub(crate) use impl_sqlite_string_enum;
@@ -1,4 +1,8 @@
 export enum TextGenerationProvider {
   OPENAI = 'openai',
+  OPENAI_COMPATIBLE = 'openai-compatible',
   GEMINI = 'gemini',
+  OLLAMA = 'ollama',
+  LLAMA_CPP = 'llama-cpp',
+  ANTHROPIC = 'anthropic',
 }
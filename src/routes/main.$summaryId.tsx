@@ -6,7 +6,8 @@ import rehypeKatex from 'rehype-katex'
 import remarkMath from 'remark-math'
 import { useLocalStorage } from 'usehooks-ts'
 
-import { Role, useSummaryStore } from '../stores/summary-store'
+import { Role, TranscriptSegment, useSummaryStore } from '../stores/summary-store'
+import { command } from '../utils/tauri'
 
 function useResizablePanel(initialWidth = 700) {
   const [width, setWidth] = useLocalStorage('summaryPanelWidth', initialWidth)
@@ -43,44 +44,105 @@ function useResizablePanel(initialWidth = 700) {
   return { width, isResizing, startResizing }
 }
 
+function formatTimestamp(seconds: number) {
+  const minutes = Math.floor(seconds / 60)
+  return `${minutes}:${String(Math.floor(seconds % 60)).padStart(2, '0')}`
+}
+
+const TranscriptView = ({ summaryId }: { summaryId: string }) => {
+  const [segments, setSegments] = useState<TranscriptSegment[] | null>(null)
+
+  useEffect(() => {
+    setSegments(null)
+    command<TranscriptSegment[]>('get_transcript', { summaryId })
+      .then(setSegments)
+      .catch(() => setSegments([]))
+  }, [summaryId])
+
+  if (!segments) return <div className="text-sm text-gray-400">Loading transcript...</div>
+  if (segments.length === 0) return <div className="text-sm text-gray-400">No transcript available.</div>
+
+  return (
+    <div className="space-y-3 text-sm text-gray-800 select-text">
+      {segments.map((segment, index) => {
+        // Consecutive segments of the same speaker read as one paragraph
+        const showSpeaker = segment.speaker && segment.speaker !== segments[index - 1]?.speaker
+
+        return (
+          <div key={segment.id}>
+            {showSpeaker && <div className="mt-4 font-semibold text-indigo-600">{segment.speaker}</div>}
+            <div className="flex gap-3">
+              <span className="shrink-0 w-12 text-xs text-gray-400 tabular-nums pt-0.5">
+                {formatTimestamp(segment.startTime)}
+              </span>
+              <p>{segment.text.trim()}</p>
+            </div>
+          </div>
+        )
+      })}
+    </div>
+  )
+}
+
 const SummaryPanel = ({ content, width }: { content: string; width: number }) => {
   const { summaryId } = Route.useParams()
   const { getSummaryById } = useSummaryStore()
+  const [tab, setTab] = useState<'summary' | 'transcript'>('summary')
 
   const summary = getSummaryById(summaryId)!
 
   return (
     <div className="shrink-0 flex flex-col border-r border-gray-200 bg-white h-full" style={{ width }}>
+      <div className="flex gap-1 border-b border-gray-200 px-6 pt-3 shrink-0 print:hidden">
+        {(['summary', 'transcript'] as const).map(value => (
+          <button
+            key={value}
+            onClick={() => setTab(value)}
+            className={`px-3 py-2 text-sm font-medium capitalize border-b-2 -mb-px transition-colors ${
+              tab === value ? 'border-indigo-600 text-indigo-600' : 'border-transparent text-gray-500 hover:text-gray-800'
+            }`}
+          >
+            {value}
+          </button>
+        ))}
+      </div>
+
       {/* --- CONTENT AREA --- */}
       <div className="flex-1 overflow-y-auto custom-scrollbar bg-white">
-        <div className="p-8" id="summary-panel">
-          <div className="hidden print:block mb-8 border-b pb-4">
-            <h1 className="text-2xl font-bold text-gray-900">{summary.title}</h1>
+        {tab === 'transcript' ? (
+          <div className="p-8">
+            <TranscriptView summaryId={summaryId} />
           </div>
+        ) : (
+          <div className="p-8" id="summary-panel">
+            <div className="hidden print:block mb-8 border-b pb-4">
+              <h1 className="text-2xl font-bold text-gray-900">{summary.title}</h1>
+            </div>
 
-          <div className="prose prose-slate prose-sm max-w-none select-text">
-            <ReactMarkdown
-              remarkPlugins={[remarkMath]}
-              rehypePlugins={[rehypeKatex]}
-              components={{
-                code: ({ ...props }) => (
-                  <code
-                    className="bg-gray-100 text-red-500 rounded px-1 py-0.5 print:bg-transparent print:border print:border-gray-200"
-                    {...props}
-                  />
-                ),
-                pre: ({ ...props }) => (
-                  <pre
-                    className="bg-slate-900 text-slate-50 rounded-lg p-4 overflow-x-auto print:bg-gray-50 print:text-gray-900 print:border print:break-inside-avoid"
-                    {...props}
-                  />
-                ),
-              }}
-            >
-              {content}
-            </ReactMarkdown>
+            <div className="prose prose-slate prose-sm max-w-none select-text">
+              <ReactMarkdown
+                remarkPlugins={[remarkMath]}
+                rehypePlugins={[rehypeKatex]}
+                components={{
+                  code: ({ ...props }) => (
+                    <code
+                      className="bg-gray-100 text-red-500 rounded px-1 py-0.5 print:bg-transparent print:border print:border-gray-200"
+                      {...props}
+                    />
+                  ),
+                  pre: ({ ...props }) => (
+                    <pre
+                      className="bg-slate-900 text-slate-50 rounded-lg p-4 overflow-x-auto print:bg-gray-50 print:text-gray-900 print:border print:break-inside-avoid"
+                      {...props}
+                    />
+                  ),
+                }}
+              >
+                {content}
+              </ReactMarkdown>
+            </div>
           </div>
-        </div>
+        )}
       </div>
     </div>
   )
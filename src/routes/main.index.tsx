@@ -57,9 +57,10 @@ function RouteComponent() {
   const navigate = useNavigate()
 
   const [selectedFile, setSelectedFile] = useState<SelectedFile | null>(null)
-  const [language, setLanguage] = useState<string>('')
+  const [language, setLanguage] = useState<string>('auto')
   const [_, setTextGenerationProvider] = useSettings('model.textGeneration.provider', 'gemini')
   const [model, setModel] = useSettings('model.textGeneration.model', models[0]?.id || '')
+  const [diarization, setDiarization] = useSettings('transcription.diarization', false)
 
   const handleFileSelect = (file: SelectedFile | null) => {
     setSelectedFile(file)
@@ -124,6 +125,20 @@ function RouteComponent() {
             </SelectContent>
           </Select>
         </div>
+
+        <div className="space-y-1">
+          <Label htmlFor="diarization">
+            <input
+              id="diarization"
+              type="checkbox"
+              className="h-4 w-4 accent-primary"
+              checked={diarization}
+              onChange={e => setDiarization(e.target.checked)}
+            />
+            Identify speakers
+          </Label>
+          <p className="text-sm text-muted-foreground">Label who is speaking in the transcript.</p>
+        </div>
       </div>
 
       <div className="sticky bottom-0 border-t bg-background pt-4">
@@ -6,12 +6,20 @@ import { Progress } from '../components/ui/progress'
 import { Summary, useSummaryStore } from '../stores/summary-store'
 
 interface SummarizationProgress {
+  jobId: string
   currentStep: number
   totalSteps: number
+  stepProgress?: number
+  etaSeconds?: number
   message: string
   summary?: Summary
 }
 
+function formatEta(seconds: number) {
+  const minutes = Math.floor(seconds / 60)
+  return minutes > 0 ? `${minutes}m ${seconds % 60}s left` : `${seconds}s left`
+}
+
 export const Route = createFileRoute('/main/progress')({
   component: RouteComponent,
 })
@@ -24,6 +32,13 @@ function RouteComponent() {
     message: 'Starting...',
   })
   const navigate = useNavigate()
+  // A step without its own progress has just started, so it counts as 0% of that step
+  const percent = progress.summary
+    ? 100
+    : Math.max(
+        0,
+        ((progress.currentStep - 1 + (progress.stepProgress ?? 0) / 100) / progress.totalSteps) * 100,
+      )
 
   useEffect(() => {
     const event = listen<SummarizationProgress>("summarization_progress", event => {
@@ -49,10 +64,11 @@ function RouteComponent() {
         <div className="flex justify-between items-center text-sm">
           <span className="font-medium">{progress.message}</span>
           <span className="text-muted-foreground">
-            {Math.round((progress.currentStep / progress.totalSteps) * 100)}%
+            {progress.etaSeconds !== undefined && `${formatEta(progress.etaSeconds)} · `}
+            {Math.round(percent)}%
           </span>
         </div>
-        <Progress value={(progress.currentStep / progress.totalSteps) * 100} className="w-full h-2" />
+        <Progress value={percent} className="w-full h-2" />
       </div>
     </div>
   )
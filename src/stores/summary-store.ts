@@ -25,11 +25,85 @@ export interface Summary {
   id: string
   title: string
   language: string
+  languageConfidence: number | null
+  transcriptionMode: 'transcribe' | 'translate'
+  speechToTextModel: string | null
+  transcriptionSettings: TranscriptionSettings | null
+  mediaInfo: MediaInfo | null
+  audioSelection: AudioSelection | null
+  sources: SourceFile[] | null
+  startTime: number | null
+  endTime: number | null
   summary: string
   filePath: string
   createdAt: Date
 }
 
+export interface TranscriptionSettings {
+  strategy: 'greedy' | 'beam-search'
+  beamSize: number
+  temperature: number
+  temperatureIncrement: number
+  threads: number | null
+  useGpu: boolean
+  gpuDevice: number
+  noSpeechThreshold: number
+  maxSegmentLength: number | null
+}
+
+export interface SourceFile {
+  path: string
+  offset: number | null
+  duration: number | null
+}
+
+export interface AudioStreamInfo {
+  index: number
+  codec: string | null
+  sampleRate: number | null
+  channels: number | null
+  language: string | null
+  title: string | null
+}
+
+export interface MediaInfo {
+  fileSize: number
+  container: string | null
+  duration: number | null
+  codecs: string[]
+  audioStreams: AudioStreamInfo[]
+  title: string | null
+  recordedAt: string | null
+  tags: Record<string, string>
+}
+
+export interface AudioSelection {
+  streams: number[]
+  channels: number[]
+  separateTracks: boolean
+}
+
+export interface TranscriptWord {
+  id: string
+  transcriptId: string
+  position: number
+  text: string
+  startTime: number
+  endTime: number
+  probability: number
+}
+
+export interface TranscriptSegment {
+  id: string
+  summaryId: string
+  text: string
+  startTime: number
+  endTime: number
+  isOriginal: boolean
+  speaker: string | null
+  words: TranscriptWord[]
+}
+
 export interface Chat {
   id: string
   summaryId: string